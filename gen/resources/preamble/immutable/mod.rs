@@ -154,29 +154,13 @@ impl Frame {
 	/// "dedupe" rollbacks, by returning `true` for all but one of each unique
 	/// frame ID. The value returned at index `i` corresponds to `self.id[i]`.
 	pub fn rollbacks(&self, keep: Rollbacks) -> Vec<bool> {
-		use Rollbacks::*;
-		match keep {
-			ExceptFirst => self.rollbacks_(self.id.values_iter().enumerate()),
-			ExceptLast => self.rollbacks_(self.id.values_iter().enumerate().rev()),
-		}
+		frame::rollbacks(self.id.values(), keep)
 	}
 
-	fn rollbacks_<'a>(&self, ids: impl Iterator<Item = (usize, &'a i32)>) -> Vec<bool> {
-		let mut result = vec![false; self.len()];
-		let unique_id_count = self.id.values_iter().max().map_or(0, |idx| {
-			1 + usize::try_from(idx - frame::FIRST_INDEX).unwrap()
-		});
-		let mut seen = vec![false; unique_id_count];
-		for (idx, id) in ids {
-			let zero_based_id = usize::try_from(id - frame::FIRST_INDEX).unwrap();
-			if !seen[zero_based_id] {
-				seen[zero_based_id] = true;
-				result[idx] = false;
-			} else {
-				result[idx] = true;
-			}
-		}
-		result
+	/// Index of the frame with ID `id`. If the frame was rolled back, this is the index of the
+	/// first or last occurrence, according to `keep`.
+	pub fn index_of(&self, id: i32, keep: Rollbacks) -> Option<usize> {
+		frame::index_of(self.id.values(), id, keep)
 	}
 }
 
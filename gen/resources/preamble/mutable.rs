@@ -5,10 +5,14 @@
 #![allow(unused_variables)]
 #![allow(dead_code)]
 
+mod slippi;
+
 use arrow2::{
-	array::{MutableArray, MutablePrimitiveArray},
-	bitmap::MutableBitmap,
-	offset::Offsets,
+	array::{MutableArray, MutablePrimitiveArray, PrimitiveArray},
+	bitmap::{Bitmap, MutableBitmap},
+	buffer::Buffer,
+	offset::{Offsets, OffsetsBuffer},
+	types::NativeType,
 };
 
 use byteorder::ReadBytesExt;
@@ -16,12 +20,68 @@ use std::io::Result;
 
 use crate::{
 	io::slippi::Version,
-	frame::{transpose, PortOccupancy},
+	frame::{self, immutable, transpose, PortOccupancy, Rollbacks},
 	game::Port,
 };
 
 type BE = byteorder::BigEndian;
 
+/// Copies part of a mutable array into a new immutable one.
+trait Snapshot {
+	type Immutable;
+
+	fn snapshot(&self, offset: usize, length: usize) -> Self::Immutable;
+}
+
+impl<T: NativeType> Snapshot for MutablePrimitiveArray<T> {
+	type Immutable = PrimitiveArray<T>;
+
+	fn snapshot(&self, offset: usize, length: usize) -> Self::Immutable {
+		PrimitiveArray::new(
+			self.data_type().clone(),
+			self.values()[offset..offset + length].to_vec().into(),
+			self.validity().map(|v| v.snapshot(offset, length)),
+		)
+	}
+}
+
+impl Snapshot for MutableBitmap {
+	type Immutable = Bitmap;
+
+	fn snapshot(&self, offset: usize, length: usize) -> Self::Immutable {
+		self.iter().skip(offset).take(length).collect()
+	}
+}
+
+impl Snapshot for Offsets<i32> {
+	type Immutable = OffsetsBuffer<i32>;
+
+	/// Offsets for `length` frames starting at `offset`, rebased to start at zero.
+	fn snapshot(&self, offset: usize, length: usize) -> Self::Immutable {
+		let offsets = &self.as_slice()[offset..=offset + length];
+		let buffer: Vec<_> = offsets.iter().map(|o| o - offsets[0]).collect();
+		OffsetsBuffer::try_from(Buffer::from(buffer)).unwrap()
+	}
+}
+
+/// Snapshots per-frame events (such as items) for `length` frames starting at `offset`.
+fn snapshot_events<T, I>(
+	events: Option<&T>,
+	offsets: Option<&Offsets<i32>>,
+	offset: usize,
+	length: usize,
+	snapshot: impl Fn(&T, usize, usize) -> I,
+) -> (Option<I>, Option<OffsetsBuffer<i32>>) {
+	events
+		.zip(offsets)
+		.map(|(events, offsets)| {
+			let start = offsets.as_slice()[offset] as usize;
+			let end = offsets.as_slice()[offset + length] as usize;
+			(snapshot(events, start, end - start), offsets.snapshot(offset, length))
+		})
+		.unzip()
+}
+
 /// Frame data for a single character (ICs are two characters).
 pub struct Data {
 	pub pre: Pre,
@@ -57,6 +117,14 @@ impl Data {
 			post: self.post.transpose_one(i, version),
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Data {
+		immutable::Data {
+			pre: self.pre.snapshot(offset, length),
+			post: self.post.snapshot(offset, length),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// Frame data for a single port.
@@ -90,6 +158,14 @@ impl PortData {
 			follower: self.follower.as_ref().map(|f| f.transpose_one(i, version)),
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::PortData {
+		immutable::PortData {
+			port: self.port,
+			leader: self.leader.snapshot(offset, length),
+			follower: self.follower.as_ref().map(|f| f.snapshot(offset, length)),
+		}
+	}
 }
 
 /// All frame data for a single game, in struct-of-arrays format.
@@ -175,4 +251,64 @@ impl Frame {
 			}),
 		}
 	}
+
+	/// See [`immutable::Frame::rollbacks`].
+	pub fn rollbacks(&self, keep: Rollbacks) -> Vec<bool> {
+		frame::rollbacks(self.id.values(), keep)
+	}
+
+	/// See [`immutable::Frame::index_of`].
+	pub fn index_of(&self, id: i32, keep: Rollbacks) -> Option<usize> {
+		frame::index_of(self.id.values(), id, keep)
+	}
+
+	/// Copies `length` frames starting at `offset` into immutable arrays, without consuming the
+	/// mutable data (e.g. to analyze a live game so far). All of those frames must be closed.
+	///
+	/// Repeated snapshots can copy just the frames added since the last one, leaving it to the
+	/// caller to combine them.
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Frame {
+		let (item, item_offset) = snapshot_events(
+			self.item.as_ref(),
+			self.item_offset.as_ref(),
+			offset,
+			length,
+			Item::snapshot,
+		);
+		let (fod_platform, fod_platform_offset) = snapshot_events(
+			self.fod_platform.as_ref(),
+			self.fod_platform_offset.as_ref(),
+			offset,
+			length,
+			FodPlatform::snapshot,
+		);
+		let (dreamland_whispy, dreamland_whispy_offset) = snapshot_events(
+			self.dreamland_whispy.as_ref(),
+			self.dreamland_whispy_offset.as_ref(),
+			offset,
+			length,
+			DreamlandWhispy::snapshot,
+		);
+		let (stadium_transformation, stadium_transformation_offset) = snapshot_events(
+			self.stadium_transformation.as_ref(),
+			self.stadium_transformation_offset.as_ref(),
+			offset,
+			length,
+			StadiumTransformation::snapshot,
+		);
+		immutable::Frame {
+			id: self.id.snapshot(offset, length),
+			ports: self.ports.iter().map(|p| p.snapshot(offset, length)).collect(),
+			start: self.start.as_ref().map(|x| x.snapshot(offset, length)),
+			end: self.end.as_ref().map(|x| x.snapshot(offset, length)),
+			item,
+			item_offset,
+			fod_platform,
+			fod_platform_offset,
+			dreamland_whispy,
+			dreamland_whispy_offset,
+			stadium_transformation,
+			stadium_transformation_offset,
+		}
+	}
 }
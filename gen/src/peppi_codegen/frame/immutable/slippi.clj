@@ -8,17 +8,21 @@
   [[nm _]]
   [:use (list "crate" "frame" "immutable" nm)])
 
+(defn value
+  [target]
+  [:method-call
+   target
+   "value"
+   ["i"]])
+
 (defn write-field-primitive
-  [target {ty :type}]
+  [value target {ty :type}]
   [:method-call
    {:unwrap true
     :generics (when-not (#{"u8" "i8"} ty) ["BE"])}
    "w"
    (str "write_" ty)
-   [[:method-call
-     target
-     "value"
-     ["i"]]]])
+   [(value target)]])
 
 (defn write-field-composite
   [target field]
@@ -29,15 +33,15 @@
    ["w" "version" "i"]])
 
 (defn write-field
-  [{idx :index, nm :name, ty :type, ver :version, :as field}]
+  [value {idx :index, nm :name, ty :type, ver :version, :as field}]
   (let [target (cond-> [:field-get "self" (or nm idx)]
                  ver ((comp unwrap as-ref)))]
     (cond
-      (primitive-types ty) (write-field-primitive target field)
+      (primitive-types ty) (write-field-primitive value target field)
       ty                   (write-field-composite target field))))
 
 (defn write-fn
-  [fields]
+  [value fields]
   [:fn
    {:ret ["Result" "()"]
     :generics ["W: Write"]}
@@ -47,7 +51,7 @@
     ["version" "Version"]
     ["i" "usize"]]
    (->> fields
-        (nested-version-ifs write-field)
+        (nested-version-ifs (partial write-field value))
         (into [:block])
         (append [:struct-init "Ok" [[nil [:unit]]]]))])
 
@@ -71,7 +75,7 @@
 
 (defn struct-impl
   [[nm {:keys [fields]}]]
-  [:impl nm [(write-fn fields)
+  [:impl nm [(write-fn value fields)
              (size-fn fields)]])
 
 (defn -main []
@@ -198,6 +198,33 @@
                                 [[:method-call "v" "push" ["true"]]]]]])
      true (append [:struct-init "Ok" [[nil [:unit]]]]))])
 
+(defn snapshot-field
+  [{idx :index, nm :name, ver :version}]
+  (let [real-target [:field-get "self" (or nm idx)]
+        target (if ver "x" real-target)
+        value [:method-call target "snapshot" ["offset" "length"]]]
+    (if ver
+      (wrap-map (as-ref real-target) "x" value)
+      value)))
+
+(defn snapshot-fn
+  [nm fields]
+  (let [ctype (list "immutable" nm)]
+    [:fn
+     {:visibility "pub"
+      :ret ctype}
+     "snapshot"
+     [["&self"]
+      ["offset" "usize"]
+      ["length" "usize"]]
+     [:block
+      [:struct-init ctype (cond->> (mapv (juxt :name snapshot-field) fields)
+                            (named? fields) (append ["validity"
+                                                     (wrap-map
+                                                      (as-ref [:field-get "self" "validity"])
+                                                      "v"
+                                                      [:method-call "v" "snapshot" ["offset" "length"]])]))]]]))
+
 (defn struct-field
   [{nm :name, ty :type, ver :version, desc :description}]
   [:struct-field
@@ -240,7 +267,8 @@
              (len-fn fields)
              (push-null-fn fields)
              (read-push-fn fields)
-             (immutable/transpose-one-fn nm fields)]])
+             (immutable/transpose-one-fn nm fields)
+             (snapshot-fn nm fields)]])
 
 (defn -main []
   (doseq [decl (mapcat (juxt struct-decl struct-impl) (read-structs))]
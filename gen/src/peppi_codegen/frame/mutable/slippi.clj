@@ -0,0 +1,21 @@
+(ns peppi-codegen.frame.mutable.slippi
+  (:require
+   [peppi-codegen.common :refer :all]
+   [peppi-codegen.frame.common :refer :all]
+   [peppi-codegen.frame.immutable.slippi :as immutable]))
+
+(defn use-statement
+  [[nm _]]
+  [:use (list "crate" "frame" "mutable" nm)])
+
+(defn value
+  [target]
+  [:subscript [:method-call target "values"] "i"])
+
+(defn struct-impl
+  [[nm {:keys [fields]}]]
+  [:impl nm [(immutable/write-fn value fields)]])
+
+(defn -main []
+  (doseq [decl (mapcat (juxt use-statement struct-impl) (read-structs))]
+    (println (emit-expr decl) "\n")))
@@ -0,0 +1,87 @@
+//! Flattened frame data, for tabular formats like CSV.
+//!
+//! Nested structs are flattened into one column per field, named by joining the path to the field
+//! with underscores. Ports are named by port number, so P1's leader's X position (`ports` → `P1` →
+//! `leader` → `post` → `position` → `x`) becomes `p1_leader_post_position_x`.
+//!
+//! The columns are derived from [`Frame::into_struct_array`], so they vary the same way: fields
+//! added in later Slippi versions are only present for replays of those versions, and `follower`
+//! columns are only present for ICs.
+
+use arrow2::{
+	array::{Array, StructArray},
+	bitmap::Bitmap,
+	datatypes::DataType,
+};
+
+use crate::{
+	frame::{immutable::Frame, PortOccupancy},
+	io::slippi::Version,
+};
+
+/// Flattens frame data into named columns. Per-frame lists (items & stage data) can't be
+/// flattened, so they're returned as-is.
+///
+/// A row is null in a given column wherever the field or any of its parents is null (e.g. all
+/// `follower` columns, on frames where Nana is absent).
+pub fn columns(
+	frames: Frame,
+	version: Version,
+	ports: &[PortOccupancy],
+) -> Vec<(String, Box<dyn Array>)> {
+	paths(frames, version, ports)
+		.into_iter()
+		.map(|(path, array)| (path.join("_"), array))
+		.collect()
+}
+
+/// Like [`columns`], but returns the path to each field (e.g. `["p1", "leader", "post",
+/// "position", "x"]`) rather than its name. Use this when you need to tell which struct a field
+/// belongs to, since field names can themselves contain underscores.
+pub fn paths(
+	frames: Frame,
+	version: Version,
+	ports: &[PortOccupancy],
+) -> Vec<(Vec<String>, Box<dyn Array>)> {
+	let frames = frames.into_struct_array(version, ports);
+	let mut columns = vec![];
+	for (field, array) in frames.fields().iter().zip(frames.values()) {
+		if field.name == "ports" {
+			let ports = array.as_any().downcast_ref::<StructArray>().unwrap();
+			for (port, array) in ports.fields().iter().zip(ports.values()) {
+				flatten(
+					vec![port.name.to_lowercase()],
+					array.as_ref(),
+					None,
+					&mut columns,
+				);
+			}
+		} else {
+			flatten(vec![field.name.clone()], array.as_ref(), None, &mut columns);
+		}
+	}
+	columns
+}
+
+fn flatten(
+	path: Vec<String>,
+	array: &dyn Array,
+	validity: Option<Bitmap>,
+	columns: &mut Vec<(Vec<String>, Box<dyn Array>)>,
+) {
+	let validity = match (validity, array.validity()) {
+		(Some(a), Some(b)) => Some(&a & b),
+		(a, b) => a.or_else(|| b.cloned()),
+	};
+	match array.data_type() {
+		DataType::Struct(children) => {
+			let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+			for (child, values) in children.iter().zip(array.values()) {
+				let mut path = path.clone();
+				path.push(child.name.clone());
+				flatten(path, values.as_ref(), validity.clone(), columns);
+			}
+		}
+		_ => columns.push((path, array.with_validity(validity))),
+	}
+}
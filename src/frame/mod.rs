@@ -9,7 +9,9 @@
 
 use crate::game::Port;
 
+pub mod flat;
 pub mod immutable;
+pub mod item;
 pub mod mutable;
 pub mod transpose;
 
@@ -30,3 +32,40 @@ pub enum Rollbacks {
 	ExceptFirst,
 	ExceptLast,
 }
+
+/// See [`immutable::Frame::rollbacks`].
+pub(crate) fn rollbacks(ids: &[i32], keep: Rollbacks) -> Vec<bool> {
+	use Rollbacks::*;
+	match keep {
+		ExceptFirst => rollbacks_(ids, ids.iter().enumerate()),
+		ExceptLast => rollbacks_(ids, ids.iter().enumerate().rev()),
+	}
+}
+
+fn rollbacks_<'a>(ids: &[i32], order: impl Iterator<Item = (usize, &'a i32)>) -> Vec<bool> {
+	let mut result = vec![false; ids.len()];
+	let unique_id_count = ids
+		.iter()
+		.max()
+		.map_or(0, |idx| 1 + usize::try_from(idx - FIRST_INDEX).unwrap());
+	let mut seen = vec![false; unique_id_count];
+	for (idx, id) in order {
+		let zero_based_id = usize::try_from(id - FIRST_INDEX).unwrap();
+		if !seen[zero_based_id] {
+			seen[zero_based_id] = true;
+			result[idx] = false;
+		} else {
+			result[idx] = true;
+		}
+	}
+	result
+}
+
+/// See [`immutable::Frame::index_of`].
+pub(crate) fn index_of(ids: &[i32], id: i32, keep: Rollbacks) -> Option<usize> {
+	use Rollbacks::*;
+	match keep {
+		ExceptFirst => ids.iter().position(|x| *x == id),
+		ExceptLast => ids.iter().rposition(|x| *x == id),
+	}
+}
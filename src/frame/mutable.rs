@@ -7,23 +7,86 @@
 #![allow(unused_variables)]
 #![allow(dead_code)]
 
+mod slippi;
+
 use arrow2::{
-	array::{MutableArray, MutablePrimitiveArray},
-	bitmap::MutableBitmap,
-	offset::Offsets,
+	array::{MutableArray, MutablePrimitiveArray, PrimitiveArray},
+	bitmap::{Bitmap, MutableBitmap},
+	buffer::Buffer,
+	offset::{Offsets, OffsetsBuffer},
+	types::NativeType,
 };
 
 use byteorder::ReadBytesExt;
 use std::io::Result;
 
 use crate::{
-	frame::{transpose, PortOccupancy},
+	frame::{self, immutable, transpose, PortOccupancy, Rollbacks},
 	game::Port,
 	io::slippi::Version,
 };
 
 type BE = byteorder::BigEndian;
 
+/// Copies part of a mutable array into a new immutable one.
+trait Snapshot {
+	type Immutable;
+
+	fn snapshot(&self, offset: usize, length: usize) -> Self::Immutable;
+}
+
+impl<T: NativeType> Snapshot for MutablePrimitiveArray<T> {
+	type Immutable = PrimitiveArray<T>;
+
+	fn snapshot(&self, offset: usize, length: usize) -> Self::Immutable {
+		PrimitiveArray::new(
+			self.data_type().clone(),
+			self.values()[offset..offset + length].to_vec().into(),
+			self.validity().map(|v| v.snapshot(offset, length)),
+		)
+	}
+}
+
+impl Snapshot for MutableBitmap {
+	type Immutable = Bitmap;
+
+	fn snapshot(&self, offset: usize, length: usize) -> Self::Immutable {
+		self.iter().skip(offset).take(length).collect()
+	}
+}
+
+impl Snapshot for Offsets<i32> {
+	type Immutable = OffsetsBuffer<i32>;
+
+	/// Offsets for `length` frames starting at `offset`, rebased to start at zero.
+	fn snapshot(&self, offset: usize, length: usize) -> Self::Immutable {
+		let offsets = &self.as_slice()[offset..=offset + length];
+		let buffer: Vec<_> = offsets.iter().map(|o| o - offsets[0]).collect();
+		OffsetsBuffer::try_from(Buffer::from(buffer)).unwrap()
+	}
+}
+
+/// Snapshots per-frame events (such as items) for `length` frames starting at `offset`.
+fn snapshot_events<T, I>(
+	events: Option<&T>,
+	offsets: Option<&Offsets<i32>>,
+	offset: usize,
+	length: usize,
+	snapshot: impl Fn(&T, usize, usize) -> I,
+) -> (Option<I>, Option<OffsetsBuffer<i32>>) {
+	events
+		.zip(offsets)
+		.map(|(events, offsets)| {
+			let start = offsets.as_slice()[offset] as usize;
+			let end = offsets.as_slice()[offset + length] as usize;
+			(
+				snapshot(events, start, end - start),
+				offsets.snapshot(offset, length),
+			)
+		})
+		.unzip()
+}
+
 /// Frame data for a single character (ICs are two characters).
 pub struct Data {
 	pub pre: Pre,
@@ -59,6 +122,14 @@ impl Data {
 			post: self.post.transpose_one(i, version),
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Data {
+		immutable::Data {
+			pre: self.pre.snapshot(offset, length),
+			post: self.post.snapshot(offset, length),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// Frame data for a single port.
@@ -92,6 +163,14 @@ impl PortData {
 			follower: self.follower.as_ref().map(|f| f.transpose_one(i, version)),
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::PortData {
+		immutable::PortData {
+			port: self.port,
+			leader: self.leader.snapshot(offset, length),
+			follower: self.follower.as_ref().map(|f| f.snapshot(offset, length)),
+		}
+	}
 }
 
 /// All frame data for a single game, in struct-of-arrays format.
@@ -222,6 +301,70 @@ impl Frame {
 			}),
 		}
 	}
+
+	/// See [`immutable::Frame::rollbacks`].
+	pub fn rollbacks(&self, keep: Rollbacks) -> Vec<bool> {
+		frame::rollbacks(self.id.values(), keep)
+	}
+
+	/// See [`immutable::Frame::index_of`].
+	pub fn index_of(&self, id: i32, keep: Rollbacks) -> Option<usize> {
+		frame::index_of(self.id.values(), id, keep)
+	}
+
+	/// Copies `length` frames starting at `offset` into immutable arrays, without consuming the
+	/// mutable data (e.g. to analyze a live game so far). All of those frames must be closed.
+	///
+	/// Repeated snapshots can copy just the frames added since the last one, leaving it to the
+	/// caller to combine them.
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Frame {
+		let (item, item_offset) = snapshot_events(
+			self.item.as_ref(),
+			self.item_offset.as_ref(),
+			offset,
+			length,
+			Item::snapshot,
+		);
+		let (fod_platform, fod_platform_offset) = snapshot_events(
+			self.fod_platform.as_ref(),
+			self.fod_platform_offset.as_ref(),
+			offset,
+			length,
+			FodPlatform::snapshot,
+		);
+		let (dreamland_whispy, dreamland_whispy_offset) = snapshot_events(
+			self.dreamland_whispy.as_ref(),
+			self.dreamland_whispy_offset.as_ref(),
+			offset,
+			length,
+			DreamlandWhispy::snapshot,
+		);
+		let (stadium_transformation, stadium_transformation_offset) = snapshot_events(
+			self.stadium_transformation.as_ref(),
+			self.stadium_transformation_offset.as_ref(),
+			offset,
+			length,
+			StadiumTransformation::snapshot,
+		);
+		immutable::Frame {
+			id: self.id.snapshot(offset, length),
+			ports: self
+				.ports
+				.iter()
+				.map(|p| p.snapshot(offset, length))
+				.collect(),
+			start: self.start.as_ref().map(|x| x.snapshot(offset, length)),
+			end: self.end.as_ref().map(|x| x.snapshot(offset, length)),
+			item,
+			item_offset,
+			fod_platform,
+			fod_platform_offset,
+			dreamland_whispy,
+			dreamland_whispy_offset,
+			stadium_transformation,
+			stadium_transformation_offset,
+		}
+	}
 }
 
 /// This event only occurs on Dreamland 64, and is sent whenever Whispy changes blow directions.
@@ -264,6 +407,13 @@ impl DreamlandWhispy {
 			direction: self.direction.values()[i],
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::DreamlandWhispy {
+		immutable::DreamlandWhispy {
+			direction: self.direction.snapshot(offset, length),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// Information about the end of the game.
@@ -318,6 +468,16 @@ impl End {
 			latest_finalized_frame: self.latest_finalized_frame.as_ref().map(|x| x.values()[i]),
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::End {
+		immutable::End {
+			latest_finalized_frame: self
+				.latest_finalized_frame
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// This event only occurs on Fountain of Dreams, and is sent for each change in platform height. If both platforms are moving, there will be two events per frame.
@@ -366,6 +526,14 @@ impl FodPlatform {
 			height: self.height.values()[i],
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::FodPlatform {
+		immutable::FodPlatform {
+			platform: self.platform.snapshot(offset, length),
+			height: self.height.snapshot(offset, length),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// An active item (includes projectiles).
@@ -488,6 +656,26 @@ impl Item {
 			instance_id: self.instance_id.as_ref().map(|x| x.values()[i]),
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Item {
+		immutable::Item {
+			r#type: self.r#type.snapshot(offset, length),
+			state: self.state.snapshot(offset, length),
+			direction: self.direction.snapshot(offset, length),
+			velocity: self.velocity.snapshot(offset, length),
+			position: self.position.snapshot(offset, length),
+			damage: self.damage.snapshot(offset, length),
+			timer: self.timer.snapshot(offset, length),
+			id: self.id.snapshot(offset, length),
+			misc: self.misc.as_ref().map(|x| x.snapshot(offset, length)),
+			owner: self.owner.as_ref().map(|x| x.snapshot(offset, length)),
+			instance_id: self
+				.instance_id
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// Miscellaneous item state.
@@ -536,6 +724,15 @@ impl ItemMisc {
 			self.3.values()[i],
 		)
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::ItemMisc {
+		immutable::ItemMisc(
+			self.0.snapshot(offset, length),
+			self.1.snapshot(offset, length),
+			self.2.snapshot(offset, length),
+			self.3.snapshot(offset, length),
+		)
+	}
 }
 
 /// 2D position.
@@ -582,6 +779,14 @@ impl Position {
 			y: self.y.values()[i],
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Position {
+		immutable::Position {
+			x: self.x.snapshot(offset, length),
+			y: self.y.snapshot(offset, length),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// Post-frame update data, for making decisions about game states (such as computing stats).
@@ -831,6 +1036,50 @@ impl Post {
 			instance_id: self.instance_id.as_ref().map(|x| x.values()[i]),
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Post {
+		immutable::Post {
+			character: self.character.snapshot(offset, length),
+			state: self.state.snapshot(offset, length),
+			position: self.position.snapshot(offset, length),
+			direction: self.direction.snapshot(offset, length),
+			percent: self.percent.snapshot(offset, length),
+			shield: self.shield.snapshot(offset, length),
+			last_attack_landed: self.last_attack_landed.snapshot(offset, length),
+			combo_count: self.combo_count.snapshot(offset, length),
+			last_hit_by: self.last_hit_by.snapshot(offset, length),
+			stocks: self.stocks.snapshot(offset, length),
+			state_age: self.state_age.as_ref().map(|x| x.snapshot(offset, length)),
+			state_flags: self
+				.state_flags
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			misc_as: self.misc_as.as_ref().map(|x| x.snapshot(offset, length)),
+			airborne: self.airborne.as_ref().map(|x| x.snapshot(offset, length)),
+			ground: self.ground.as_ref().map(|x| x.snapshot(offset, length)),
+			jumps: self.jumps.as_ref().map(|x| x.snapshot(offset, length)),
+			l_cancel: self.l_cancel.as_ref().map(|x| x.snapshot(offset, length)),
+			hurtbox_state: self
+				.hurtbox_state
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			velocities: self.velocities.as_ref().map(|x| x.snapshot(offset, length)),
+			hitlag: self.hitlag.as_ref().map(|x| x.snapshot(offset, length)),
+			animation_index: self
+				.animation_index
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			last_hit_by_instance: self
+				.last_hit_by_instance
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			instance_id: self
+				.instance_id
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// Pre-frame update data, required to reconstruct a replay.
@@ -991,6 +1240,39 @@ impl Pre {
 			raw_analog_cstick_y: self.raw_analog_cstick_y.as_ref().map(|x| x.values()[i]),
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Pre {
+		immutable::Pre {
+			random_seed: self.random_seed.snapshot(offset, length),
+			state: self.state.snapshot(offset, length),
+			position: self.position.snapshot(offset, length),
+			direction: self.direction.snapshot(offset, length),
+			joystick: self.joystick.snapshot(offset, length),
+			cstick: self.cstick.snapshot(offset, length),
+			triggers: self.triggers.snapshot(offset, length),
+			buttons: self.buttons.snapshot(offset, length),
+			buttons_physical: self.buttons_physical.snapshot(offset, length),
+			triggers_physical: self.triggers_physical.snapshot(offset, length),
+			raw_analog_x: self
+				.raw_analog_x
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			percent: self.percent.as_ref().map(|x| x.snapshot(offset, length)),
+			raw_analog_y: self
+				.raw_analog_y
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			raw_analog_cstick_x: self
+				.raw_analog_cstick_x
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			raw_analog_cstick_y: self
+				.raw_analog_cstick_y
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// This event only occurs on Pokemon Stadium, and is sent whenever the transformation event or transformation type changes.
@@ -1039,6 +1321,14 @@ impl StadiumTransformation {
 			r#type: self.r#type.values()[i],
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::StadiumTransformation {
+		immutable::StadiumTransformation {
+			event: self.event.snapshot(offset, length),
+			r#type: self.r#type.snapshot(offset, length),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// Initialization data such as game mode, settings, characters & stage.
@@ -1094,6 +1384,17 @@ impl Start {
 			scene_frame_counter: self.scene_frame_counter.as_ref().map(|x| x.values()[i]),
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Start {
+		immutable::Start {
+			random_seed: self.random_seed.snapshot(offset, length),
+			scene_frame_counter: self
+				.scene_frame_counter
+				.as_ref()
+				.map(|x| x.snapshot(offset, length)),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// Miscellaneous state flags.
@@ -1147,6 +1448,16 @@ impl StateFlags {
 			self.4.values()[i],
 		)
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::StateFlags {
+		immutable::StateFlags(
+			self.0.snapshot(offset, length),
+			self.1.snapshot(offset, length),
+			self.2.snapshot(offset, length),
+			self.3.snapshot(offset, length),
+			self.4.snapshot(offset, length),
+		)
+	}
 }
 
 /// Trigger state.
@@ -1193,6 +1504,14 @@ impl TriggersPhysical {
 			r: self.r.values()[i],
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::TriggersPhysical {
+		immutable::TriggersPhysical {
+			l: self.l.snapshot(offset, length),
+			r: self.r.snapshot(offset, length),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// Self-induced and knockback velocities.
@@ -1260,6 +1579,17 @@ impl Velocities {
 			self_x_ground: self.self_x_ground.values()[i],
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Velocities {
+		immutable::Velocities {
+			self_x_air: self.self_x_air.snapshot(offset, length),
+			self_y: self.self_y.snapshot(offset, length),
+			knockback_x: self.knockback_x.snapshot(offset, length),
+			knockback_y: self.knockback_y.snapshot(offset, length),
+			self_x_ground: self.self_x_ground.snapshot(offset, length),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
 
 /// 2D velocity.
@@ -1306,4 +1636,12 @@ impl Velocity {
 			y: self.y.values()[i],
 		}
 	}
+
+	pub fn snapshot(&self, offset: usize, length: usize) -> immutable::Velocity {
+		immutable::Velocity {
+			x: self.x.snapshot(offset, length),
+			y: self.y.snapshot(offset, length),
+			validity: self.validity.as_ref().map(|v| v.snapshot(offset, length)),
+		}
+	}
 }
@@ -0,0 +1,431 @@
+// This file is auto-generated by `gen/scripts/frames`. Do not edit.
+
+#![allow(unused_variables)]
+
+use std::io::{Result, Write};
+
+use byteorder::WriteBytesExt;
+
+use crate::{
+	frame::{
+		mutable::{Data, Frame, PortData},
+		PortOccupancy,
+	},
+	io::slippi::{de::Event, Version},
+};
+
+type BE = byteorder::BigEndian;
+
+impl Data {
+	fn write_pre<W: Write>(
+		&self,
+		w: &mut W,
+		version: Version,
+		idx: usize,
+		frame_id: i32,
+		port: PortOccupancy,
+	) -> Result<()> {
+		if self.validity.as_ref().is_none_or(|v| v.get(idx)) {
+			w.write_u8(Event::FramePre as u8)?;
+			w.write_i32::<BE>(frame_id)?;
+			w.write_u8(port.port as u8)?;
+			w.write_u8(match port.follower {
+				true => 1,
+				_ => 0,
+			})?;
+			self.pre.write(w, version, idx)?;
+		}
+		Ok(())
+	}
+
+	fn write_post<W: Write>(
+		&self,
+		w: &mut W,
+		version: Version,
+		idx: usize,
+		frame_id: i32,
+		port: PortOccupancy,
+	) -> Result<()> {
+		if self.validity.as_ref().is_none_or(|v| v.get(idx)) {
+			w.write_u8(Event::FramePost as u8)?;
+			w.write_i32::<BE>(frame_id)?;
+			w.write_u8(port.port as u8)?;
+			w.write_u8(match port.follower {
+				true => 1,
+				_ => 0,
+			})?;
+			self.post.write(w, version, idx)?;
+		}
+		Ok(())
+	}
+}
+
+impl PortData {
+	fn write_pre<W: Write>(
+		&self,
+		w: &mut W,
+		version: Version,
+		idx: usize,
+		frame_id: i32,
+	) -> Result<()> {
+		self.leader.write_pre(
+			w,
+			version,
+			idx,
+			frame_id,
+			PortOccupancy {
+				port: self.port,
+				follower: false,
+			},
+		)?;
+		self.follower.as_ref().map_or(Ok(()), |f| {
+			if f.validity.as_ref().is_none_or(|v| v.get(idx)) {
+				f.write_pre(
+					w,
+					version,
+					idx,
+					frame_id,
+					PortOccupancy {
+						port: self.port,
+						follower: true,
+					},
+				)
+			} else {
+				Ok(())
+			}
+		})
+	}
+
+	fn write_post<W: Write>(
+		&self,
+		w: &mut W,
+		version: Version,
+		idx: usize,
+		frame_id: i32,
+	) -> Result<()> {
+		self.leader.write_post(
+			w,
+			version,
+			idx,
+			frame_id,
+			PortOccupancy {
+				port: self.port,
+				follower: false,
+			},
+		)?;
+		self.follower.as_ref().map_or(Ok(()), |f| {
+			if f.validity.as_ref().is_none_or(|v| v.get(idx)) {
+				f.write_post(
+					w,
+					version,
+					idx,
+					frame_id,
+					PortOccupancy {
+						port: self.port,
+						follower: true,
+					},
+				)
+			} else {
+				Ok(())
+			}
+		})
+	}
+}
+
+impl Frame {
+	/// Writes the events for the frame at index `idx`, which must be closed (i.e. all of its
+	/// events have been parsed).
+	pub fn write_one<W: Write>(&self, w: &mut W, version: Version, idx: usize) -> Result<()> {
+		let frame_id = self.id.values()[idx];
+		if version.gte(2, 2) {
+			w.write_u8(Event::FrameStart as u8)?;
+			w.write_i32::<BE>(frame_id)?;
+			self.start.as_ref().unwrap().write(w, version, idx)?;
+		}
+		for port in &self.ports {
+			port.write_pre(w, version, idx, frame_id)?;
+		}
+		if version.gte(3, 18) {
+			// FOD platform
+			let offset = self.fod_platform_offset.as_ref().unwrap();
+			let (start, end) = offset.start_end(idx);
+			for evt_idx in start..end {
+				w.write_u8(Event::FodPlatform as u8)?;
+				w.write_i32::<BE>(frame_id)?;
+				self.fod_platform
+					.as_ref()
+					.unwrap()
+					.write(w, version, evt_idx)?;
+			}
+
+			// Dreamland Whispy
+			let offset = self.dreamland_whispy_offset.as_ref().unwrap();
+			let (start, end) = offset.start_end(idx);
+			for evt_idx in start..end {
+				w.write_u8(Event::DreamlandWhispy as u8)?;
+				w.write_i32::<BE>(frame_id)?;
+				self.dreamland_whispy
+					.as_ref()
+					.unwrap()
+					.write(w, version, evt_idx)?;
+			}
+
+			// Stadium transformation
+			let offset = self.stadium_transformation_offset.as_ref().unwrap();
+			let (start, end) = offset.start_end(idx);
+			for evt_idx in start..end {
+				w.write_u8(Event::StadiumTransformation as u8)?;
+				w.write_i32::<BE>(frame_id)?;
+				self.stadium_transformation
+					.as_ref()
+					.unwrap()
+					.write(w, version, evt_idx)?;
+			}
+		}
+		if version.gte(3, 0) {
+			let offset = self.item_offset.as_ref().unwrap();
+			let (start, end) = offset.start_end(idx);
+			for item_idx in start..end {
+				w.write_u8(Event::Item as u8)?;
+				w.write_i32::<BE>(frame_id)?;
+				self.item.as_ref().unwrap().write(w, version, item_idx)?;
+			}
+		}
+		for port in &self.ports {
+			port.write_post(w, version, idx, frame_id)?;
+		}
+		if version.gte(3, 0) {
+			w.write_u8(Event::FrameEnd as u8)?;
+			w.write_i32::<BE>(frame_id)?;
+			self.end.as_ref().unwrap().write(w, version, idx)?;
+		}
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::DreamlandWhispy;
+
+impl DreamlandWhispy {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_u8(self.direction.values()[i])?;
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::End;
+
+impl End {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		if version.gte(3, 7) {
+			w.write_i32::<BE>(self.latest_finalized_frame.as_ref().unwrap().values()[i])?
+		};
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::FodPlatform;
+
+impl FodPlatform {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_u8(self.platform.values()[i])?;
+		w.write_f32::<BE>(self.height.values()[i])?;
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::Item;
+
+impl Item {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_u16::<BE>(self.r#type.values()[i])?;
+		w.write_u8(self.state.values()[i])?;
+		w.write_f32::<BE>(self.direction.values()[i])?;
+		self.velocity.write(w, version, i)?;
+		self.position.write(w, version, i)?;
+		w.write_u16::<BE>(self.damage.values()[i])?;
+		w.write_f32::<BE>(self.timer.values()[i])?;
+		w.write_u32::<BE>(self.id.values()[i])?;
+		if version.gte(3, 2) {
+			self.misc.as_ref().unwrap().write(w, version, i)?;
+			if version.gte(3, 6) {
+				w.write_i8(self.owner.as_ref().unwrap().values()[i])?;
+				if version.gte(3, 16) {
+					w.write_u16::<BE>(self.instance_id.as_ref().unwrap().values()[i])?
+				}
+			}
+		};
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::ItemMisc;
+
+impl ItemMisc {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_u8(self.0.values()[i])?;
+		w.write_u8(self.1.values()[i])?;
+		w.write_u8(self.2.values()[i])?;
+		w.write_u8(self.3.values()[i])?;
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::Position;
+
+impl Position {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_f32::<BE>(self.x.values()[i])?;
+		w.write_f32::<BE>(self.y.values()[i])?;
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::Post;
+
+impl Post {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_u8(self.character.values()[i])?;
+		w.write_u16::<BE>(self.state.values()[i])?;
+		self.position.write(w, version, i)?;
+		w.write_f32::<BE>(self.direction.values()[i])?;
+		w.write_f32::<BE>(self.percent.values()[i])?;
+		w.write_f32::<BE>(self.shield.values()[i])?;
+		w.write_u8(self.last_attack_landed.values()[i])?;
+		w.write_u8(self.combo_count.values()[i])?;
+		w.write_u8(self.last_hit_by.values()[i])?;
+		w.write_u8(self.stocks.values()[i])?;
+		if version.gte(0, 2) {
+			w.write_f32::<BE>(self.state_age.as_ref().unwrap().values()[i])?;
+			if version.gte(2, 0) {
+				self.state_flags.as_ref().unwrap().write(w, version, i)?;
+				w.write_f32::<BE>(self.misc_as.as_ref().unwrap().values()[i])?;
+				w.write_u8(self.airborne.as_ref().unwrap().values()[i])?;
+				w.write_u16::<BE>(self.ground.as_ref().unwrap().values()[i])?;
+				w.write_u8(self.jumps.as_ref().unwrap().values()[i])?;
+				w.write_u8(self.l_cancel.as_ref().unwrap().values()[i])?;
+				if version.gte(2, 1) {
+					w.write_u8(self.hurtbox_state.as_ref().unwrap().values()[i])?;
+					if version.gte(3, 5) {
+						self.velocities.as_ref().unwrap().write(w, version, i)?;
+						if version.gte(3, 8) {
+							w.write_f32::<BE>(self.hitlag.as_ref().unwrap().values()[i])?;
+							if version.gte(3, 11) {
+								w.write_u32::<BE>(
+									self.animation_index.as_ref().unwrap().values()[i],
+								)?;
+								if version.gte(3, 16) {
+									w.write_u16::<BE>(
+										self.last_hit_by_instance.as_ref().unwrap().values()[i],
+									)?;
+									w.write_u16::<BE>(
+										self.instance_id.as_ref().unwrap().values()[i],
+									)?
+								}
+							}
+						}
+					}
+				}
+			}
+		};
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::Pre;
+
+impl Pre {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_u32::<BE>(self.random_seed.values()[i])?;
+		w.write_u16::<BE>(self.state.values()[i])?;
+		self.position.write(w, version, i)?;
+		w.write_f32::<BE>(self.direction.values()[i])?;
+		self.joystick.write(w, version, i)?;
+		self.cstick.write(w, version, i)?;
+		w.write_f32::<BE>(self.triggers.values()[i])?;
+		w.write_u32::<BE>(self.buttons.values()[i])?;
+		w.write_u16::<BE>(self.buttons_physical.values()[i])?;
+		self.triggers_physical.write(w, version, i)?;
+		if version.gte(1, 2) {
+			w.write_i8(self.raw_analog_x.as_ref().unwrap().values()[i])?;
+			if version.gte(1, 4) {
+				w.write_f32::<BE>(self.percent.as_ref().unwrap().values()[i])?;
+				if version.gte(3, 15) {
+					w.write_i8(self.raw_analog_y.as_ref().unwrap().values()[i])?;
+					if version.gte(3, 17) {
+						w.write_i8(self.raw_analog_cstick_x.as_ref().unwrap().values()[i])?;
+						w.write_i8(self.raw_analog_cstick_y.as_ref().unwrap().values()[i])?
+					}
+				}
+			}
+		};
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::StadiumTransformation;
+
+impl StadiumTransformation {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_u16::<BE>(self.event.values()[i])?;
+		w.write_u16::<BE>(self.r#type.values()[i])?;
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::Start;
+
+impl Start {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_u32::<BE>(self.random_seed.values()[i])?;
+		if version.gte(3, 10) {
+			w.write_u32::<BE>(self.scene_frame_counter.as_ref().unwrap().values()[i])?
+		};
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::StateFlags;
+
+impl StateFlags {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_u8(self.0.values()[i])?;
+		w.write_u8(self.1.values()[i])?;
+		w.write_u8(self.2.values()[i])?;
+		w.write_u8(self.3.values()[i])?;
+		w.write_u8(self.4.values()[i])?;
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::TriggersPhysical;
+
+impl TriggersPhysical {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_f32::<BE>(self.l.values()[i])?;
+		w.write_f32::<BE>(self.r.values()[i])?;
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::Velocities;
+
+impl Velocities {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_f32::<BE>(self.self_x_air.values()[i])?;
+		w.write_f32::<BE>(self.self_y.values()[i])?;
+		w.write_f32::<BE>(self.knockback_x.values()[i])?;
+		w.write_f32::<BE>(self.knockback_y.values()[i])?;
+		w.write_f32::<BE>(self.self_x_ground.values()[i])?;
+		Ok(())
+	}
+}
+
+use crate::frame::mutable::Velocity;
+
+impl Velocity {
+	fn write<W: Write>(&self, w: &mut W, version: Version, i: usize) -> Result<()> {
+		w.write_f32::<BE>(self.x.values()[i])?;
+		w.write_f32::<BE>(self.y.values()[i])?;
+		Ok(())
+	}
+}
@@ -0,0 +1,344 @@
+//! Feature extraction for machine learning.
+//!
+//! [`extract`] turns a game's frame data into fixed-width `f32` samples from one player's point of
+//! view (`self`), with the other players (`opponent1`, `opponent2`, …) following in port order.
+//! Each frame contributes the same features, named by [`Features::names`]:
+//!
+//! * `stage`: the stage, one-hot encoded (`stage_0`, `stage_1`, …) unless [`Opts::raw_ids`].
+//! * `{who}_position_{x,y}`: position, normalized to `[-1, 1]` within the stage's blast zones if
+//!   [`Opts::normalize`] is set (see [`Opts::bounds`] for stages without known blast zones).
+//! * `{who}_direction`, `{who}_percent`, `{who}_shield`, `{who}_stocks`: as in [`Post`].
+//! * `{who}_joystick_{x,y}`, `{who}_cstick_{x,y}`, `{who}_triggers`: as in [`Pre`].
+//! * `{who}_character`, `{who}_state`: internal character ID & action state, one-hot encoded
+//!   unless [`Opts::raw_ids`]. Out-of-range IDs (e.g. from mods) are encoded as all zeroes.
+//! * `{opponent}_d{x,y}`, `{opponent}_distance`: the opponent's position relative to `self`'s,
+//!   in the same units as positions (if [`Opts::relative`] is set).
+//!
+//! Only leaders are included (i.e. Nana is ignored).
+//!
+//! [`Pre`]: crate::frame::transpose::Pre
+//! [`Post`]: crate::frame::transpose::Post
+
+use crate::{
+	frame::{immutable::PortData, Rollbacks},
+	game::{immutable::Game, Port},
+	io::{err, Result},
+};
+
+/// A stage's blast zones, used to normalize positions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+	pub left: f32,
+	pub right: f32,
+	pub top: f32,
+	pub bottom: f32,
+}
+
+impl Bounds {
+	/// Used for stages [`Bounds::for_stage`] doesn't know: the union of the tournament-legal
+	/// stages' blast zones.
+	pub const DEFAULT: Self = Self {
+		left: -255.0,
+		right: 255.0,
+		top: 250.0,
+		bottom: -146.25,
+	};
+
+	/// Blast zones for tournament-legal stages.
+	pub fn for_stage(stage: u16) -> Option<Self> {
+		let (left, right, top, bottom) = match stage {
+			2 => (-198.75, 198.75, 202.5, -146.25), // Fountain of Dreams
+			3 => (-230.0, 230.0, 180.0, -111.0),    // Pokémon Stadium
+			8 => (-175.7, 173.6, 168.0, -91.0),     // Yoshi's Story
+			28 => (-255.0, 255.0, 250.0, -123.0),   // Dream Land N64
+			31 => (-224.0, 224.0, 200.0, -108.8),   // Battlefield
+			32 => (-246.0, 246.0, 188.0, -140.0),   // Final Destination
+			_ => return None,
+		};
+		Some(Self {
+			left,
+			right,
+			top,
+			bottom,
+		})
+	}
+
+	/// The same blast zones, flipped horizontally.
+	fn mirrored(self) -> Self {
+		Self {
+			left: -self.right,
+			right: -self.left,
+			..self
+		}
+	}
+
+	fn x(&self, x: f32) -> f32 {
+		2.0 * (x - self.left) / (self.right - self.left) - 1.0
+	}
+
+	fn y(&self, y: f32) -> f32 {
+		2.0 * (y - self.bottom) / (self.top - self.bottom) - 1.0
+	}
+}
+
+/// Options for feature extraction.
+#[derive(Clone, Debug)]
+pub struct Opts {
+	/// Whose point of view to extract features from. Defaults to the lowest occupied port.
+	pub port: Option<Port>,
+	/// Normalize positions by the stage's blast zones.
+	pub normalize: bool,
+	/// Blast zones to normalize by, overriding [`Bounds::for_stage`]. Stages it doesn't know
+	/// fall back to [`Bounds::DEFAULT`].
+	pub bounds: Option<Bounds>,
+	/// Include opponents' positions relative to `self`.
+	pub relative: bool,
+	/// Encode characters, stages & action states as plain IDs rather than one-hot.
+	pub raw_ids: bool,
+	/// Number of consecutive frames per sample.
+	pub window: usize,
+	/// Number of frames between the starts of consecutive samples.
+	pub stride: usize,
+	/// Also produce a horizontally mirrored copy of every sample (for data augmentation).
+	pub mirror: bool,
+	/// Drop rolled-back frames, keeping only one of each frame ID. See
+	/// [`Frame::rollbacks`](crate::frame::immutable::Frame::rollbacks).
+	pub rollbacks: Option<Rollbacks>,
+}
+
+impl Default for Opts {
+	fn default() -> Self {
+		Self {
+			port: None,
+			normalize: true,
+			bounds: None,
+			relative: true,
+			raw_ids: false,
+			window: 1,
+			stride: 1,
+			mirror: false,
+			rollbacks: None,
+		}
+	}
+}
+
+/// Extracted features: a sequence of equal-width samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Features {
+	/// Name of each per-frame feature.
+	pub names: Vec<String>,
+	/// Frames per sample.
+	pub window: usize,
+	/// All samples, concatenated. Each sample is `window` frames of `names.len()` features.
+	pub data: Vec<f32>,
+	/// ID of the first frame of each sample.
+	pub ids: Vec<i32>,
+	/// Whether each sample is mirrored. Mirrored samples follow all unmirrored ones.
+	pub mirrored: Vec<bool>,
+}
+
+impl Features {
+	/// Number of samples.
+	pub fn len(&self) -> usize {
+		self.ids.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.ids.is_empty()
+	}
+
+	/// Number of values per sample.
+	pub fn width(&self) -> usize {
+		self.window * self.names.len()
+	}
+
+	/// The `i`th sample.
+	pub fn sample(&self, i: usize) -> &[f32] {
+		&self.data[i * self.width()..(i + 1) * self.width()]
+	}
+}
+
+/// Number of one-hot classes for each ID type: one past the highest internal character ID
+/// (Sandbag), stage ID (Final Destination), and action state ID (across all characters).
+const CHARACTERS: usize = 33;
+const STAGES: usize = 33;
+const STATES: usize = 538;
+
+/// Builds a single frame's features. Names are only recorded for the first frame.
+struct Row<'a> {
+	values: &'a mut Vec<f32>,
+	names: Option<&'a mut Vec<String>>,
+}
+
+impl Row<'_> {
+	fn push(&mut self, name: impl FnOnce() -> String, value: f32) {
+		if let Some(names) = self.names.as_mut() {
+			names.push(name());
+		}
+		self.values.push(value);
+	}
+
+	fn id(&mut self, name: &str, id: usize, classes: usize, raw: bool) {
+		match raw {
+			true => self.push(|| name.to_string(), id as f32),
+			false => {
+				for i in 0..classes {
+					self.push(|| format!("{}_{}", name, i), (i == id).into());
+				}
+			}
+		}
+	}
+}
+
+/// Extracts features from a game's frame data. See the [module docs](self) for details.
+pub fn extract(game: &Game, opts: Option<&Opts>) -> Result<Features> {
+	let default = Opts::default();
+	let opts = opts.unwrap_or(&default);
+	if opts.window == 0 || opts.stride == 0 {
+		return Err(err!("window & stride must be positive"));
+	}
+
+	let ports = &game.frames.ports;
+	if ports.is_empty() {
+		return Err(err!("no players"));
+	}
+	let this = match opts.port {
+		Some(port) => ports
+			.iter()
+			.position(|p| p.port == port)
+			.ok_or_else(|| err!("no such port: {}", port))?,
+		None => 0,
+	};
+	let ports: Vec<_> = std::iter::once(&ports[this])
+		.chain(
+			ports
+				.iter()
+				.enumerate()
+				.filter(|(i, _)| *i != this)
+				.map(|(_, p)| p),
+		)
+		.collect();
+
+	let bounds = opts.normalize.then(|| {
+		opts.bounds
+			.or_else(|| Bounds::for_stage(game.start.stage))
+			.unwrap_or(Bounds::DEFAULT)
+	});
+
+	let skip = opts.rollbacks.map(|keep| game.frames.rollbacks(keep));
+	let rows: Vec<_> = (0..game.frames.len())
+		.filter(|i| !skip.as_ref().is_some_and(|s| s[*i]))
+		.collect();
+
+	let mut names = vec![];
+	let mut features = Features {
+		names: vec![],
+		window: opts.window,
+		data: vec![],
+		ids: vec![],
+		mirrored: vec![],
+	};
+	for mirror in [false, true] {
+		if mirror && !opts.mirror {
+			break;
+		}
+		let bounds = bounds.map(|b| match mirror {
+			true => b.mirrored(),
+			false => b,
+		});
+		let mut frames = vec![];
+		for &i in &rows {
+			let mut row = Row {
+				values: &mut frames,
+				names: (names.is_empty()).then_some(&mut names),
+			};
+			frame(&mut row, game, &ports, i, mirror, bounds, opts);
+		}
+
+		let width = names.len();
+		let mut start = 0;
+		while start + opts.window <= rows.len() {
+			features
+				.data
+				.extend_from_slice(&frames[start * width..(start + opts.window) * width]);
+			features.ids.push(game.frames.id.value(rows[start]));
+			features.mirrored.push(mirror);
+			start += opts.stride;
+		}
+	}
+	features.names = names;
+
+	Ok(features)
+}
+
+fn frame(
+	row: &mut Row,
+	game: &Game,
+	ports: &[&PortData],
+	i: usize,
+	mirror: bool,
+	bounds: Option<Bounds>,
+	opts: &Opts,
+) {
+	// mirroring flips the stage around x = 0
+	let sign = match mirror {
+		true => -1.0,
+		false => 1.0,
+	};
+	let scale = |b: Bounds| (2.0 / (b.right - b.left), 2.0 / (b.top - b.bottom));
+
+	row.id("stage", game.start.stage.into(), STAGES, opts.raw_ids);
+
+	let this = &ports[0].leader.post.position;
+	let (this_x, this_y) = (sign * this.x.value(i), this.y.value(i));
+	for (n, port) in ports.iter().enumerate() {
+		let who = match n {
+			0 => "self".to_string(),
+			n => format!("opponent{}", n),
+		};
+		let (pre, post) = (&port.leader.pre, &port.leader.post);
+
+		let (x, y) = (sign * post.position.x.value(i), post.position.y.value(i));
+		let (nx, ny) = match bounds {
+			Some(b) => (b.x(x), b.y(y)),
+			None => (x, y),
+		};
+		row.push(|| format!("{}_position_x", who), nx);
+		row.push(|| format!("{}_position_y", who), ny);
+		row.push(
+			|| format!("{}_direction", who),
+			sign * post.direction.value(i),
+		);
+		row.push(|| format!("{}_percent", who), post.percent.value(i));
+		row.push(|| format!("{}_shield", who), post.shield.value(i));
+		row.push(|| format!("{}_stocks", who), post.stocks.value(i).into());
+		row.push(
+			|| format!("{}_joystick_x", who),
+			sign * pre.joystick.x.value(i),
+		);
+		row.push(|| format!("{}_joystick_y", who), pre.joystick.y.value(i));
+		row.push(|| format!("{}_cstick_x", who), sign * pre.cstick.x.value(i));
+		row.push(|| format!("{}_cstick_y", who), pre.cstick.y.value(i));
+		row.push(|| format!("{}_triggers", who), pre.triggers.value(i));
+		row.id(
+			&format!("{}_character", who),
+			post.character.value(i).into(),
+			CHARACTERS,
+			opts.raw_ids,
+		);
+		row.id(
+			&format!("{}_state", who),
+			post.state.value(i).into(),
+			STATES,
+			opts.raw_ids,
+		);
+
+		if n > 0 && opts.relative {
+			let (sx, sy) = bounds.map_or((1.0, 1.0), scale);
+			let (dx, dy) = (sx * (x - this_x), sy * (y - this_y));
+			row.push(|| format!("{}_dx", who), dx);
+			row.push(|| format!("{}_dy", who), dy);
+			row.push(|| format!("{}_distance", who), dx.hypot(dy));
+		}
+	}
+}
@@ -0,0 +1,278 @@
+//! Parsing of the Gecko codes recorded in Slippi replays (added: v3.3).
+//!
+//! Slippi records the full list of codes injected into the game, concatenated with no terminator.
+//! The raw [`GeckoCodes`] blob remains the source of truth for round-tripping, since Slippi doesn't
+//! zero the padding after the last code. [`GeckoCodes::from_codes`] reproduces everything up to
+//! `actual_size`.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	game::GeckoCodes,
+	io::{err, Result},
+};
+
+/// Slippi splits the Gecko code list into blocks of this size.
+const BLOCK_SIZE: usize = 512;
+
+/// Gecko code types, ignoring the low bit (which is part of the address) and the pointer bit (see
+/// [`GeckoCode::uses_pointer`]).
+pub mod code_type {
+	pub const WRITE_8: u8 = 0x00;
+	pub const WRITE_16: u8 = 0x02;
+	pub const WRITE_32: u8 = 0x04;
+	pub const WRITE_STRING: u8 = 0x06;
+	pub const WRITE_SERIAL: u8 = 0x08;
+	pub const EXECUTE_ASM: u8 = 0xC0;
+	pub const INSERT_ASM: u8 = 0xC2;
+	pub const BRANCH: u8 = 0xC6;
+	pub const FULL_TERMINATOR: u8 = 0xE0;
+	pub const END_IF: u8 = 0xE2;
+	pub const END: u8 = 0xF0;
+	pub const INSERT_ASM_CHECKSUM: u8 = 0xF2;
+	pub const INSERT_ASM_CHECKSUM_ALT: u8 = 0xF4;
+}
+
+/// Codes below this can be relative to the pointer register (`po`) instead of the base address
+/// (`ba`), which is indicated by [`POINTER_BIT`].
+const POINTER_TYPES: u8 = 0xE0;
+
+const POINTER_BIT: u8 = 0x10;
+
+/// Code type of a code starting with `b` (see [`code_type`]).
+fn base_type(b: u8) -> u8 {
+	match b & 0xFE {
+		t if t < POINTER_TYPES => t & !POINTER_BIT,
+		t => t,
+	}
+}
+
+/// A single Gecko code.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GeckoCode {
+	/// Raw bytes of the code, including the header line
+	pub bytes: Vec<u8>,
+}
+
+// Accessors return `None` if `bytes` is too short, which can only happen for codes that weren't
+// produced by `GeckoCodes::codes`.
+impl GeckoCode {
+	/// Code type (e.g. [`code_type::INSERT_ASM`]).
+	pub fn code_type(&self) -> Option<u8> {
+		self.bytes.first().map(|b| base_type(*b))
+	}
+
+	/// Whether the code is relative to the pointer register (`po`) rather than the base address.
+	pub fn uses_pointer(&self) -> Option<bool> {
+		self.bytes
+			.first()
+			.map(|b| *b < POINTER_TYPES && b & POINTER_BIT != 0)
+	}
+
+	/// Target address in game memory, or the offset from the pointer if [`uses_pointer`].
+	///
+	/// [`uses_pointer`]: Self::uses_pointer
+	pub fn address(&self) -> Option<u32> {
+		let header = u32::from_be_bytes(self.bytes.get(0..4)?.try_into().unwrap());
+		Some(match self.uses_pointer()? {
+			true => header & 0x01FFFFFF,
+			false => 0x80000000 | (header & 0x01FFFFFF),
+		})
+	}
+
+	/// Everything after the code's first word (the value for writes, instructions for ASM codes).
+	pub fn payload(&self) -> Option<&[u8]> {
+		self.bytes.get(4..)
+	}
+
+	/// Which well-known code this belongs to, if any.
+	pub fn known(&self) -> Option<KnownCode> {
+		if self.uses_pointer()? {
+			return None;
+		}
+		let (code_type, address) = (self.code_type()?, self.address()?);
+		KNOWN_CODES
+			.iter()
+			.find(|(_, t, a, lines)| {
+				*t == code_type
+					&& *a == address
+					&& lines.is_none_or(|n| self.bytes.len() == 8 + n * 8)
+			})
+			.map(|(k, _, _, _)| *k)
+	}
+}
+
+/// Size in bytes of the code starting at `b`, which is `pos` bytes into the list.
+///
+/// Fails if `b` is too short to tell, or if the code type's size isn't known (guessing would
+/// misalign every code after it).
+fn code_size(b: &[u8], pos: usize) -> Result<usize> {
+	use code_type::*;
+	let truncated = || err!("Gecko codes: truncated code at {}", pos);
+	let word = |i: usize| {
+		b.get(i..i + 4)
+			.map(|w| u32::from_be_bytes(w.try_into().unwrap()))
+			.ok_or_else(truncated)
+	};
+	let size = match base_type(*b.first().ok_or_else(truncated)?) {
+		WRITE_8 | WRITE_16 | WRITE_32 => 8,
+		WRITE_STRING => 8 + (word(4)? as usize).div_ceil(8) * 8,
+		WRITE_SERIAL => 16,
+		// if (32 & 16 bit)
+		0x20..=0x2E => 8,
+		// base address & pointer
+		0x40..=0x4E => 8,
+		// repeat, return, goto, gosub
+		0x60..=0x68 => 8,
+		// Gecko registers & memory copies
+		0x80..=0x8C => 8,
+		// if (Gecko registers & counters)
+		0xA0..=0xAE => 8,
+		EXECUTE_ASM | INSERT_ASM => 8 + word(4)? as usize * 8,
+		// branch, on/off switch, address range check
+		BRANCH | 0xCC | 0xCE => 8,
+		FULL_TERMINATOR | END_IF | END => 8,
+		INSERT_ASM_CHECKSUM | INSERT_ASM_CHECKSUM_ALT => {
+			8 + *b.get(7).ok_or_else(truncated)? as usize * 8
+		}
+		t => return Err(err!("Gecko codes: unknown code type {:#04X} at {}", t, pos)),
+	};
+	match size <= b.len() {
+		true => Ok(size),
+		false => Err(truncated()),
+	}
+}
+
+/// Versions of the Universal Controller Fix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum UcfVersion {
+	V0_8,
+	V0_84,
+}
+
+/// Well-known codes, identified by their injection points (and for UCF, their lengths).
+///
+/// Codes not listed here are left unidentified; compare against a reference list with
+/// [`GeckoCodes::diff`] to find those.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum KnownCode {
+	/// Universal Controller Fix (dashback & shield drop)
+	Ucf(UcfVersion),
+	/// Frozen Pokémon Stadium (no transformations)
+	FrozenPokemonStadium,
+	/// Lag reduction (polling drift fix)
+	LagReduction,
+	/// Slippi's netplay codes. Slippi injects these even for offline games.
+	SlippiOnline,
+}
+
+/// (code, code type, address, number of instruction lines if significant)
+const KNOWN_CODES: &[(KnownCode, u8, u32, Option<usize>)] = &[
+	// UCF versions share injection points, but their dashback & shield drop lengths differ
+	(
+		KnownCode::Ucf(UcfVersion::V0_8),
+		code_type::INSERT_ASM,
+		0x800C9A44,
+		Some(0x2B),
+	),
+	(
+		KnownCode::Ucf(UcfVersion::V0_8),
+		code_type::INSERT_ASM,
+		0x800998A4,
+		Some(0x26),
+	),
+	(
+		KnownCode::Ucf(UcfVersion::V0_84),
+		code_type::INSERT_ASM,
+		0x800C9A44,
+		Some(0x20),
+	),
+	(
+		KnownCode::Ucf(UcfVersion::V0_84),
+		code_type::INSERT_ASM,
+		0x800998A4,
+		Some(0x19),
+	),
+	(
+		KnownCode::FrozenPokemonStadium,
+		code_type::INSERT_ASM,
+		0x801D14C8,
+		None,
+	),
+	(
+		KnownCode::LagReduction,
+		code_type::WRITE_32,
+		0x80019860,
+		None,
+	),
+	// connect code entry screen
+	(
+		KnownCode::SlippiOnline,
+		code_type::INSERT_ASM,
+		0x8023CCA4,
+		None,
+	),
+];
+
+/// Differences between two sets of codes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diff {
+	/// Codes present only in the second set
+	pub added: Vec<GeckoCode>,
+	/// Codes present only in the first set
+	pub removed: Vec<GeckoCode>,
+}
+
+impl Diff {
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty()
+	}
+}
+
+impl GeckoCodes {
+	/// Parses the raw blob into individual codes.
+	pub fn codes(&self) -> Result<Vec<GeckoCode>> {
+		let actual_size = self.actual_size as usize;
+		let mut b = self
+			.bytes
+			.get(..actual_size)
+			.ok_or_else(|| err!("Gecko codes: actual size {} out of bounds", actual_size))?;
+		let mut codes = vec![];
+		while !b.is_empty() {
+			let size = code_size(b, actual_size - b.len())?;
+			codes.push(GeckoCode {
+				bytes: b[..size].to_vec(),
+			});
+			b = &b[size..];
+		}
+		Ok(codes)
+	}
+
+	/// Builds the raw blob (zero-padded to Slippi's block size) from a list of codes.
+	pub fn from_codes(codes: &[GeckoCode]) -> Self {
+		let mut bytes: Vec<u8> = codes.iter().flat_map(|c| c.bytes.iter().copied()).collect();
+		let actual_size = u32::try_from(bytes.len()).unwrap();
+		bytes.resize(bytes.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+		Self { bytes, actual_size }
+	}
+
+	/// Well-known codes present in this list.
+	pub fn known(&self) -> Result<Vec<KnownCode>> {
+		let mut known: Vec<_> = self.codes()?.iter().filter_map(|c| c.known()).collect();
+		known.sort();
+		known.dedup();
+		Ok(known)
+	}
+
+	/// Codes added & removed going from `self` to `other`, in list order.
+	pub fn diff(&self, other: &GeckoCodes) -> Result<Diff> {
+		let (a, b) = (self.codes()?, other.codes()?);
+		let (a_set, b_set): (HashSet<_>, HashSet<_>) = (a.iter().collect(), b.iter().collect());
+		Ok(Diff {
+			added: b.iter().filter(|c| !a_set.contains(c)).cloned().collect(),
+			removed: a.iter().filter(|c| !b_set.contains(c)).cloned().collect(),
+		})
+	}
+}
@@ -0,0 +1,133 @@
+//! Netplay health: how much a game rolled back, and how far it ran ahead of its opponents' inputs.
+//!
+//! Rollbacks are detected from repeated frame IDs (the game re-simulates frames when late inputs
+//! arrive). Lag is measured using [`End::latest_finalized_frame`](crate::frame::transpose::End)
+//! (added: v3.7), the latest frame for which all players' inputs were known.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+/// Slippi stalls rather than running more than this many frames ahead of the latest finalized
+/// frame, so reaching it means the game froze waiting for inputs.
+pub const MAX_ROLLBACK_FRAMES: u32 = 7;
+
+/// Options for computing netplay health.
+#[derive(Clone, Debug)]
+pub struct Opts {
+	/// Frames at least this far ahead of the latest finalized frame count as a lag spike.
+	pub lag_spike_threshold: u32,
+}
+
+impl Default for Opts {
+	fn default() -> Self {
+		Self {
+			lag_spike_threshold: MAX_ROLLBACK_FRAMES,
+		}
+	}
+}
+
+/// A run of consecutive frames that were all at least
+/// [`lag_spike_threshold`](Opts::lag_spike_threshold) frames ahead of the latest finalized frame.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LagSpike {
+	/// ID of the first frame in the spike
+	pub start: i32,
+
+	/// ID of the last frame in the spike (inclusive)
+	pub end: i32,
+
+	/// furthest the game got ahead of the latest finalized frame during the spike
+	pub max_unfinalized: u32,
+}
+
+/// Rollback & latency statistics for a single game.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetplayHealth {
+	/// number of distinct frames played
+	pub frames: usize,
+
+	/// number of times the game rolled back
+	pub rollbacks: usize,
+
+	/// number of rollbacks of each length, keyed by length (frames re-simulated)
+	pub rollback_lengths: BTreeMap<u32, usize>,
+
+	/// length of the longest rollback
+	pub max_rollback: u32,
+
+	/// number of frames simulated more than once (i.e. time spent re-simulating)
+	pub rollback_frames: usize,
+
+	/// furthest the game got ahead of the latest finalized frame (added: v3.7)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub max_unfinalized: Option<u32>,
+
+	/// periods where the game ran too far ahead of its opponents' inputs (added: v3.7)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub lag_spikes: Option<Vec<LagSpike>>,
+}
+
+/// Computes netplay health from a game's frame IDs, and the latest finalized frame for each of
+/// those frames (if known).
+pub fn netplay_health(
+	ids: &[i32],
+	finalized: Option<&[i32]>,
+	opts: Option<&Opts>,
+) -> NetplayHealth {
+	let threshold = opts.map_or(MAX_ROLLBACK_FRAMES, |o| o.lag_spike_threshold);
+
+	let mut rollback_lengths = BTreeMap::new();
+	let mut rollback_frames = 0;
+	let mut seen = HashSet::new();
+	for (idx, id) in ids.iter().enumerate() {
+		if idx > 0 && *id <= ids[idx - 1] {
+			let len = (ids[idx - 1] - id + 1) as u32;
+			*rollback_lengths.entry(len).or_default() += 1;
+		}
+		if !seen.insert(*id) {
+			rollback_frames += 1;
+		}
+	}
+
+	let unfinalized = finalized.map(|finalized| {
+		ids.iter()
+			.zip(finalized)
+			.map(|(id, f)| u32::try_from(id - f).unwrap_or(0))
+			.collect::<Vec<_>>()
+	});
+
+	let lag_spikes = unfinalized.as_ref().map(|unfinalized| {
+		let mut spikes: Vec<LagSpike> = vec![];
+		let mut in_spike = false;
+		for (id, u) in ids.iter().zip(unfinalized) {
+			if *u < threshold {
+				in_spike = false;
+				continue;
+			}
+			match spikes.last_mut() {
+				Some(spike) if in_spike => {
+					spike.end = *id;
+					spike.max_unfinalized = spike.max_unfinalized.max(*u);
+				}
+				_ => spikes.push(LagSpike {
+					start: *id,
+					end: *id,
+					max_unfinalized: *u,
+				}),
+			}
+			in_spike = true;
+		}
+		spikes
+	});
+
+	NetplayHealth {
+		frames: ids.len() - rollback_frames,
+		rollbacks: rollback_lengths.values().sum(),
+		max_rollback: rollback_lengths.keys().last().copied().unwrap_or(0),
+		rollback_lengths,
+		rollback_frames,
+		max_unfinalized: unfinalized.map(|u| u.into_iter().max().unwrap_or(0)),
+		lag_spikes,
+	}
+}
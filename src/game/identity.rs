@@ -0,0 +1,158 @@
+//! Validated player identifiers for Slippi Online: connect codes & Slippi UIDs.
+//!
+//! Replays store these as raw strings (see [`Netplay`]). The types here normalize them, so they
+//! can be compared, sorted, and hashed directly when joining players across replays.
+
+use std::{
+	fmt::{self, Display, Formatter},
+	str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	game::{shift_jis::MeleeString, Netplay, Player},
+	io::{err, Error, Result},
+};
+
+/// Maximum length of a connect code, excluding the `#` (the in-game buffer fits 8 characters
+/// including a full-width `＃`).
+const MAX_CODE_LEN: usize = 7;
+
+/// A Slippi Online connect code, such as `ABCD#123`.
+///
+/// Always stored in normalized form: upper-case, with an ASCII `#` separator.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ConnectCode(String);
+
+impl ConnectCode {
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+
+	/// The part before the `#`.
+	pub fn tag(&self) -> &str {
+		self.0.split_once('#').unwrap().0
+	}
+
+	/// The part after the `#`.
+	pub fn number(&self) -> &str {
+		self.0.split_once('#').unwrap().1
+	}
+}
+
+impl FromStr for ConnectCode {
+	type Err = Error;
+
+	/// Accepts either `#` or `＃` as the separator, in any case.
+	fn from_str(s: &str) -> Result<Self> {
+		let normalized = MeleeString(s.to_string())
+			.to_normalized()
+			.to_ascii_uppercase();
+		let (tag, number) = normalized
+			.split_once('#')
+			.ok_or_else(|| err!("invalid connect code (missing '#'): {}", s))?;
+		if tag.is_empty() || !tag.chars().all(|c| c.is_ascii_alphanumeric()) {
+			return Err(err!("invalid connect code tag: {}", s));
+		}
+		if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+			return Err(err!("invalid connect code number: {}", s));
+		}
+		if tag.len() + number.len() > MAX_CODE_LEN {
+			return Err(err!("connect code too long: {}", s));
+		}
+		Ok(ConnectCode(normalized))
+	}
+}
+
+impl TryFrom<String> for ConnectCode {
+	type Error = Error;
+	fn try_from(s: String) -> Result<Self> {
+		s.parse()
+	}
+}
+
+impl TryFrom<&MeleeString> for ConnectCode {
+	type Error = Error;
+	fn try_from(s: &MeleeString) -> Result<Self> {
+		s.as_str().parse()
+	}
+}
+
+impl From<ConnectCode> for String {
+	fn from(c: ConnectCode) -> Self {
+		c.0
+	}
+}
+
+impl Display for ConnectCode {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+/// A Slippi user ID (added: v3.11).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Suid(String);
+
+impl Suid {
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl FromStr for Suid {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric()) {
+			true => Ok(Suid(s.to_string())),
+			false => Err(err!("invalid Slippi UID: {}", s)),
+		}
+	}
+}
+
+impl TryFrom<String> for Suid {
+	type Error = Error;
+	fn try_from(s: String) -> Result<Self> {
+		s.parse()
+	}
+}
+
+impl From<Suid> for String {
+	fn from(s: Suid) -> Self {
+		s.0
+	}
+}
+
+impl Display for Suid {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+impl Netplay {
+	/// Validated connect code (`None` if empty or malformed).
+	pub fn connect_code(&self) -> Option<ConnectCode> {
+		ConnectCode::try_from(&self.code).ok()
+	}
+
+	/// Validated Slippi UID (`None` before v3.11, or if empty or malformed).
+	pub fn suid(&self) -> Option<Suid> {
+		self.suid.as_deref().and_then(|s| s.parse().ok())
+	}
+}
+
+impl Player {
+	/// Validated connect code, for netplay games (added: v3.9).
+	pub fn connect_code(&self) -> Option<ConnectCode> {
+		self.netplay.as_ref().and_then(|n| n.connect_code())
+	}
+
+	/// Validated Slippi UID, for netplay games (added: v3.11).
+	pub fn suid(&self) -> Option<Suid> {
+		self.netplay.as_ref().and_then(|n| n.suid())
+	}
+}
@@ -7,7 +7,7 @@ use serde_json::{Map, Value};
 
 use crate::{
 	frame::{immutable::Frame, transpose},
-	game::{self, End, GeckoCodes, Quirks, Start},
+	game::{self, health, End, GeckoCodes, Quirks, Start},
 };
 
 #[derive(Debug)]
@@ -21,6 +21,21 @@ pub struct Game {
 	pub quirks: Option<Quirks>,
 }
 
+impl Game {
+	/// Rollback & latency statistics. See [`health`].
+	pub fn netplay_health(&self, opts: Option<&health::Opts>) -> health::NetplayHealth {
+		health::netplay_health(
+			self.frames.id.values(),
+			self.frames
+				.end
+				.as_ref()
+				.and_then(|e| e.latest_finalized_frame.as_ref())
+				.map(|f| f.values().as_slice()),
+			opts,
+		)
+	}
+}
+
 impl game::Game for Game {
 	fn start(&self) -> &Start {
 		&self.start
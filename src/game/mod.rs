@@ -16,8 +16,17 @@ use crate::{
 	io::slippi::{self, Version},
 };
 
+pub mod features;
+pub mod gecko;
+pub mod health;
+pub mod identity;
 pub mod immutable;
 pub mod mutable;
+pub mod outcome;
+pub mod remap;
+pub mod rules;
+pub mod session;
+pub mod set;
 pub mod shift_jis;
 
 /// How many ports the game supports.
@@ -37,6 +46,7 @@ pub const ICE_CLIMBERS: u8 = 14;
 	Debug,
 	PartialEq,
 	Eq,
+	Hash,
 	PartialOrd,
 	Ord,
 	Serialize,
@@ -348,7 +358,7 @@ impl End {
 
 /// Binary blob of Gecko codes in use.
 ///
-/// Currently unparsed, but still needed for round-tripping.
+/// Kept as raw bytes for round-tripping. See [`gecko`] for parsing.
 #[derive(Debug, PartialEq, Eq)]
 pub struct GeckoCodes {
 	pub bytes: Vec<u8>,
@@ -367,6 +377,16 @@ pub trait Game {
 	/// Combines all data for a single frame into a struct.
 	/// Avoid calling this if you need maximum performance.
 	fn frame(&self, idx: usize) -> transpose::Frame;
+
+	/// Who won the game and how, along with each player's final stocks & percent.
+	fn outcome(&self) -> outcome::Outcome {
+		outcome::outcome(self)
+	}
+
+	/// What kind of session the game was played in (online, offline, etc), and where.
+	fn session(&self) -> session::Session {
+		session::session(self)
+	}
 }
 
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
@@ -0,0 +1,182 @@
+//! Who won a game, and how.
+//!
+//! Slippi records the game's result in several places, depending on the version: the end
+//! method (all versions), the player who quit out (added: v2.0), and player placements
+//! (added: v3.13). When those are missing or inconclusive (e.g. timeouts), we fall back to each
+//! player's final stocks & percent.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{EndMethod, Game, Port};
+
+/// A player's state at the end of the game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerOutcome {
+	pub port: Port,
+
+	/// stocks remaining on the last frame
+	pub stocks: u8,
+
+	/// percent on the last frame
+	pub percent: f32,
+
+	/// placement reported by Slippi, 0-indexed (added: v3.13)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub placement: Option<u8>,
+}
+
+/// The result of a game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Outcome {
+	/// how the game ended (`None` if there was no Game End event)
+	pub method: Option<EndMethod>,
+
+	/// player(s) who won, including teammates
+	pub winners: Vec<Port>,
+
+	/// player(s) who lost, including teammates
+	pub losers: Vec<Port>,
+
+	/// player who quit out (LRAS), if any
+	pub quitter: Option<Port>,
+
+	/// per-player final state, in port order
+	pub players: Vec<PlayerOutcome>,
+}
+
+impl Outcome {
+	/// Whether the game ended because someone quit out.
+	pub fn is_quit_out(&self) -> bool {
+		self.quitter.is_some()
+	}
+}
+
+/// Players on the same team share a key. Outside of teams mode, every player is their own team.
+fn team_key<G: Game + ?Sized>(game: &G, port: Port) -> u16 {
+	let start = game.start();
+	match start.is_teams {
+		true => start
+			.players
+			.iter()
+			.find(|p| p.port == port)
+			.and_then(|p| p.team)
+			.map_or(256 + port as u16, |t| t.color as u16),
+		false => 256 + port as u16,
+	}
+}
+
+/// Splits all players into (winners, losers), where the winners are the teams of `winners`.
+fn split<G: Game + ?Sized>(game: &G, winners: &[Port]) -> (Vec<Port>, Vec<Port>) {
+	let teams: Vec<_> = winners.iter().map(|p| team_key(game, *p)).collect();
+	game.start()
+		.players
+		.iter()
+		.map(|p| p.port)
+		.partition(|p| teams.contains(&team_key(game, *p)))
+}
+
+/// Picks the team(s) with the most stocks, breaking ties by lowest percent.
+fn best_by_stocks<G: Game + ?Sized>(game: &G, players: &[PlayerOutcome]) -> Vec<Port> {
+	let mut teams: Vec<(u16, u32, f32)> = vec![];
+	for p in players {
+		let key = team_key(game, p.port);
+		match teams.iter_mut().find(|t| t.0 == key) {
+			Some(t) => {
+				t.1 += p.stocks as u32;
+				t.2 += p.percent;
+			}
+			None => teams.push((key, p.stocks as u32, p.percent)),
+		}
+	}
+	let cmp = |a: &(u16, u32, f32), b: &(u16, u32, f32)| {
+		a.1.cmp(&b.1)
+			.then_with(|| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal))
+	};
+	let best = match teams.iter().max_by(|a, b| cmp(a, b)) {
+		Some(best) => *best,
+		None => return vec![],
+	};
+	players
+		.iter()
+		.filter(|p| {
+			let key = team_key(game, p.port);
+			teams
+				.iter()
+				.any(|t| t.0 == key && cmp(t, &best) == Ordering::Equal)
+		})
+		.map(|p| p.port)
+		.collect()
+}
+
+pub(crate) fn outcome<G: Game + ?Sized>(game: &G) -> Outcome {
+	let start = game.start();
+	let end = game.end().as_ref();
+
+	let last = match game.len() {
+		0 => None,
+		len => Some(game.frame(len - 1)),
+	};
+	let players: Vec<_> = start
+		.players
+		.iter()
+		.map(|player| {
+			let post = last
+				.as_ref()
+				.and_then(|f| f.ports.iter().find(|p| p.port == player.port))
+				.map(|p| &p.leader.post);
+			PlayerOutcome {
+				port: player.port,
+				stocks: post.map_or(player.stocks, |p| p.stocks),
+				percent: post.map_or(0.0, |p| p.percent),
+				placement: end
+					.and_then(|e| e.players.as_ref())
+					.and_then(|ps| ps.iter().find(|p| p.port == player.port))
+					.map(|p| p.placement),
+			}
+		})
+		.collect();
+
+	let method = end.map(|e| e.method);
+	let quitter = end.and_then(|e| e.lras_initiator).flatten();
+
+	let winners: Vec<Port> = if let Some(quitter) = quitter {
+		// the quitter's team loses, regardless of the game state
+		let quitter_team = team_key(game, quitter);
+		players
+			.iter()
+			.map(|p| p.port)
+			.filter(|p| team_key(game, *p) != quitter_team)
+			.collect()
+	} else {
+		match method {
+			None | Some(EndMethod::Unresolved) | Some(EndMethod::NoContest) => vec![],
+			Some(method) => {
+				let placed: Vec<_> = players
+					.iter()
+					.filter(|p| p.placement == Some(0))
+					.map(|p| p.port)
+					.collect();
+				if !placed.is_empty() && method != EndMethod::Time {
+					placed
+				} else {
+					best_by_stocks(game, &players)
+				}
+			}
+		}
+	};
+
+	let (winners, losers) = match winners.is_empty() {
+		true => (vec![], vec![]),
+		false => split(game, &winners),
+	};
+
+	Outcome {
+		method,
+		winners,
+		losers,
+		quitter,
+		players,
+	}
+}
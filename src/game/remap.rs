@@ -0,0 +1,197 @@
+//! Moving players to different ports.
+//!
+//! Analyses usually want the player they care about in P1. [`remap`] and [`normalize`] move
+//! players between ports, updating everything that refers to ports (Game Start, Game End, frame
+//! data, item owners, `last_hit_by`, and the metadata's `players`) so the result is a consistent
+//! game that can still be written with [`slippi::write`](crate::io::slippi::write).
+//!
+//! The game's [`hash`](Game::hash) is cleared, since it no longer matches.
+
+use arrow2::{array::PrimitiveArray, types::NativeType};
+use serde_json::{Map, Value};
+
+use crate::{
+	frame::immutable::Frame,
+	game::{immutable::Game, End, Port, Start, NUM_PORTS},
+	io::{
+		err,
+		slippi::{de, ser, Version},
+		Result,
+	},
+};
+
+/// Per-port regions of the raw Game Start block, as `(offset, size, (major, minor))` where the
+/// version is when the region was added. Offsets are relative to [`Start::bytes`], which doesn't
+/// include the event code.
+const START_REGIONS: [(usize, usize, (u8, u8)); 6] = [
+	(0x64, 36, (0, 0)),   // character, type, stocks, etc
+	(0x140, 8, (1, 0)),   // UCF
+	(0x160, 16, (1, 3)),  // name tag
+	(0x1A4, 31, (3, 9)),  // netplay name
+	(0x220, 10, (3, 9)),  // netplay code
+	(0x248, 29, (3, 11)), // Slippi UID
+];
+
+/// Moves players to different ports, according to `ports` (pairs of old & new ports). Players
+/// not mentioned in `ports` stay where they are.
+///
+/// Returns an error if a port in `ports` is unoccupied or mapped more than once, or if two
+/// players would end up in the same port.
+pub fn remap(game: Game, ports: &[(Port, Port)]) -> Result<Game> {
+	let occupied: Vec<_> = game.start.players.iter().map(|p| p.port).collect();
+	for (n, (from, _)) in ports.iter().enumerate() {
+		if !occupied.contains(from) {
+			return Err(err!("no player in port: {}", from));
+		}
+		if ports[..n].iter().any(|(f, _)| f == from) {
+			return Err(err!("port mapped more than once: {}", from));
+		}
+	}
+
+	// extend the mapping to a permutation of all ports, so empty ports' data moves too
+	let mut perm: [Option<Port>; NUM_PORTS] = [None; NUM_PORTS];
+	for from in &occupied {
+		let to = ports
+			.iter()
+			.find(|(f, _)| f == from)
+			.map_or(*from, |(_, t)| *t);
+		if perm.contains(&Some(to)) {
+			return Err(err!("multiple players in port: {}", to));
+		}
+		perm[*from as usize] = Some(to);
+	}
+	let mut free = (0..NUM_PORTS as u8)
+		.map(|n| Port::try_from(n).unwrap())
+		.filter(|p| !perm.contains(&Some(*p)))
+		.collect::<Vec<_>>()
+		.into_iter();
+	let perm = perm.map(|to| to.or_else(|| free.next()).unwrap());
+
+	permute(game, &perm)
+}
+
+/// Moves players to consecutive ports starting at P1, keeping their order except that `first`
+/// (if given) is moved to P1. For example, P3 vs P4 becomes P1 vs P2.
+pub fn normalize(game: Game, first: Option<Port>) -> Result<Game> {
+	let mut occupied: Vec<_> = game.start.players.iter().map(|p| p.port).collect();
+	if let Some(first) = first {
+		let idx = occupied
+			.iter()
+			.position(|p| *p == first)
+			.ok_or_else(|| err!("no player in port: {}", first))?;
+		occupied[..=idx].rotate_right(1);
+	}
+	let ports: Vec<_> = occupied
+		.into_iter()
+		.enumerate()
+		.map(|(n, p)| (p, Port::try_from(n as u8).unwrap()))
+		.collect();
+	remap(game, &ports)
+}
+
+/// Applies a permutation of ports, where `perm[n]` is the new port for port `n`.
+fn permute(game: Game, perm: &[Port; NUM_PORTS]) -> Result<Game> {
+	let version = game.start.slippi.version;
+	let port = |n: u8| {
+		Port::try_from(n)
+			.ok()
+			.map_or(n, |p| perm[p as usize].into())
+	};
+
+	Ok(Game {
+		start: start(&game.start, perm)?,
+		end: game.end.map(|e| end(&e, version, port)).transpose()?,
+		frames: frames(game.frames, perm, port),
+		metadata: game.metadata.map(|m| metadata(m, port)),
+		gecko_codes: game.gecko_codes,
+		hash: None,
+		quirks: game.quirks,
+	})
+}
+
+fn start(start: &Start, perm: &[Port; NUM_PORTS]) -> Result<Start> {
+	let version = start.slippi.version;
+	// serialize first, since the parsed fields take precedence over the raw bytes
+	let old = ser::_game_start(start)?;
+	let mut bytes = old.clone();
+	for (offset, size, (major, minor)) in START_REGIONS {
+		if version.gte(major, minor) {
+			for (n, to) in perm.iter().enumerate() {
+				bytes[offset + size * (*to as usize)..][..size]
+					.copy_from_slice(&old[offset + size * n..][..size]);
+			}
+		}
+	}
+	de::game_start(&mut &bytes[..])
+}
+
+fn end(end: &End, version: Version, port: impl Fn(u8) -> u8) -> Result<End> {
+	// serialize first, since the parsed fields take precedence over the raw bytes
+	let mut buf = vec![];
+	ser::game_end(&mut buf, end, version)?;
+	let mut bytes = end.bytes.0.clone();
+	bytes[..buf.len() - 1].copy_from_slice(&buf[1..]);
+
+	// v2.0: LRAS initiator
+	if let Some(b) = bytes.get_mut(1) {
+		*b = port(*b);
+	}
+	// v3.13: placements
+	if bytes.len() >= 2 + NUM_PORTS {
+		let old = bytes.clone();
+		for n in 0..NUM_PORTS as u8 {
+			bytes[2 + port(n) as usize] = old[2 + n as usize];
+		}
+	}
+	de::game_end(&mut &bytes[..])
+}
+
+fn map_values<T: NativeType>(array: &mut PrimitiveArray<T>, f: impl Fn(T) -> T) {
+	*array = PrimitiveArray::new(
+		array.data_type().clone(),
+		array
+			.values()
+			.iter()
+			.map(|x| f(*x))
+			.collect::<Vec<_>>()
+			.into(),
+		array.validity().cloned(),
+	);
+}
+
+fn frames(mut frames: Frame, perm: &[Port; NUM_PORTS], port: impl Fn(u8) -> u8) -> Frame {
+	for p in frames.ports.iter_mut() {
+		p.port = perm[p.port as usize];
+		map_values(&mut p.leader.post.last_hit_by, &port);
+		if let Some(follower) = p.follower.as_mut() {
+			map_values(&mut follower.post.last_hit_by, &port);
+		}
+	}
+	frames.ports.sort_by_key(|p| p.port);
+
+	if let Some(owner) = frames.item.as_mut().and_then(|i| i.owner.as_mut()) {
+		// -1 when unowned
+		map_values(owner, |o| match u8::try_from(o) {
+			Ok(o) => port(o) as i8,
+			Err(_) => o,
+		});
+	}
+
+	frames
+}
+
+/// Metadata's `players` is keyed by port number (as a string, e.g. `"0"` for P1).
+fn metadata(mut metadata: Map<String, Value>, port: impl Fn(u8) -> u8) -> Map<String, Value> {
+	if let Some(Value::Object(players)) = metadata.get_mut("players") {
+		// keep the original order, for round-tripping
+		let remapped: Vec<_> = std::mem::take(players)
+			.into_iter()
+			.map(|(k, v)| match k.parse() {
+				Ok(n) => (port(n).to_string(), v),
+				Err(_) => (k, v),
+			})
+			.collect();
+		players.extend(remapped);
+	}
+	metadata
+}
@@ -0,0 +1,252 @@
+//! Game rules & item switches, decoded from [`Start`]'s bitfields.
+//!
+//! The setters here keep [`Start::bytes`] in sync with the decoded fields, so modified rules
+//! survive re-serialization in both `.slp` and `.slpp` formats.
+//!
+//! Bit assignments come from community reverse-engineering of Melee's match setup struct. Bits
+//! without a known meaning are left untouched by the setters.
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Player, Port, Start};
+
+/// Offset of `Start::bitfield` within `Start::bytes`.
+const BITFIELD_OFFSET: usize = 0x04;
+
+/// Offset of `Start::item_spawn_bitfield` within `Start::bytes`.
+const ITEM_SPAWN_BITFIELD_OFFSET: usize = 0x27;
+
+/// Offset of the first player's `bitfield` within `Start::bytes`.
+const PLAYER_BITFIELD_OFFSET: usize = 0x70;
+
+/// Size of each player's block within `Start::bytes`.
+const PLAYER_BLOCK_SIZE: usize = 0x24;
+
+/// Win condition (`bitfield[0] >> 5`).
+#[repr(u8)]
+#[derive(
+	Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, IntoPrimitive, TryFromPrimitive,
+)]
+pub enum GameMode {
+	Time = 0,
+	Stock = 1,
+	Coin = 2,
+	Bonus = 3,
+}
+
+/// How the in-game timer behaves (`bitfield[0] & 0x03`).
+#[repr(u8)]
+#[derive(
+	Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, IntoPrimitive, TryFromPrimitive,
+)]
+pub enum TimerType {
+	None = 0,
+	Decreasing = 2,
+	Increasing = 3,
+}
+
+/// Items that can be toggled on the Item Switch screen.
+///
+/// Discriminants are Melee's item type IDs, which also determine each item's bit in
+/// `Start::item_spawn_bitfield` (read as a 40-bit big-endian integer).
+#[repr(u8)]
+#[derive(
+	Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, IntoPrimitive, TryFromPrimitive,
+)]
+pub enum ItemSwitch {
+	Capsule = 0,
+	Box = 1,
+	Barrel = 2,
+	Egg = 3,
+	PartyBall = 4,
+	BarrelCannon = 5,
+	BobOmb = 6,
+	MrSaturn = 7,
+	HeartContainer = 8,
+	MaximTomato = 9,
+	Starman = 10,
+	HomeRunBat = 11,
+	BeamSword = 12,
+	Parasol = 13,
+	GreenShell = 14,
+	RedShell = 15,
+	RayGun = 16,
+	Freezie = 17,
+	Food = 18,
+	MotionSensorBomb = 19,
+	Flipper = 20,
+	SuperScope = 21,
+	StarRod = 22,
+	LipsStick = 23,
+	Fan = 24,
+	FireFlower = 25,
+	SuperMushroom = 26,
+	PoisonMushroom = 27,
+	Hammer = 28,
+	WarpStar = 29,
+	ScrewAttack = 30,
+	BunnyHood = 31,
+	MetalBox = 32,
+	CloakingDevice = 33,
+	PokeBall = 34,
+}
+
+impl ItemSwitch {
+	/// All switchable items, in item type order.
+	pub fn all() -> impl Iterator<Item = ItemSwitch> {
+		(0..=34).map(|i| ItemSwitch::try_from(i).unwrap())
+	}
+
+	/// (byte index, mask) of this item within `Start::item_spawn_bitfield`.
+	fn bit(self) -> (usize, u8) {
+		let id = u8::from(self) as usize;
+		(4 - id / 8, 1 << (id % 8))
+	}
+}
+
+/// Per-player flags stored in `Player::bitfield`.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PlayerFlag {
+	LowGravity = 0x04,
+	Invisible = 0x08,
+	Metal = 0x10,
+	Stamina = 0x20,
+	Rumble = 0x80,
+}
+
+fn set_bit(byte: &mut u8, mask: u8, value: bool) {
+	match value {
+		true => *byte |= mask,
+		false => *byte &= !mask,
+	}
+}
+
+impl Start {
+	/// Win condition, or `None` if unrecognized.
+	pub fn mode(&self) -> Option<GameMode> {
+		GameMode::try_from(self.bitfield[0] >> 5).ok()
+	}
+
+	pub fn set_mode(&mut self, mode: GameMode) {
+		let b = (self.bitfield[0] & 0x1F) | (u8::from(mode) << 5);
+		self.set_bitfield(0, b);
+	}
+
+	/// Timer behavior, or `None` if unrecognized.
+	pub fn timer_type(&self) -> Option<TimerType> {
+		TimerType::try_from(self.bitfield[0] & 0x03).ok()
+	}
+
+	pub fn set_timer_type(&mut self, timer_type: TimerType) {
+		let b = (self.bitfield[0] & !0x03) | u8::from(timer_type);
+		self.set_bitfield(0, b);
+	}
+
+	pub fn is_friendly_fire(&self) -> bool {
+		self.bitfield[1] & 0x01 != 0
+	}
+
+	pub fn set_friendly_fire(&mut self, value: bool) {
+		self.set_bitfield_bit(1, 0x01, value);
+	}
+
+	/// Whether the score is displayed during the game.
+	pub fn is_score_displayed(&self) -> bool {
+		self.bitfield[1] & 0x20 != 0
+	}
+
+	pub fn set_score_displayed(&mut self, value: bool) {
+		self.set_bitfield_bit(1, 0x20, value);
+	}
+
+	/// Whether players can pause (Slippi Online disables this).
+	pub fn is_pause_enabled(&self) -> bool {
+		self.bitfield[2] & 0x08 == 0
+	}
+
+	pub fn set_pause_enabled(&mut self, value: bool) {
+		self.set_bitfield_bit(2, 0x08, !value);
+	}
+
+	/// Whether every player is in stamina mode.
+	pub fn is_stamina(&self) -> bool {
+		!self.players.is_empty() && self.players.iter().all(|p| p.flag(PlayerFlag::Stamina))
+	}
+
+	pub fn is_item_enabled(&self, item: ItemSwitch) -> bool {
+		let (idx, mask) = item.bit();
+		self.item_spawn_bitfield[idx] & mask != 0
+	}
+
+	pub fn set_item_enabled(&mut self, item: ItemSwitch, value: bool) {
+		let (idx, mask) = item.bit();
+		set_bit(&mut self.item_spawn_bitfield[idx], mask, value);
+		let b = self.item_spawn_bitfield[idx];
+		if let Some(raw) = self.bytes.0.get_mut(ITEM_SPAWN_BITFIELD_OFFSET + idx) {
+			*raw = b;
+		}
+	}
+
+	/// Items turned on in the Item Switch screen.
+	pub fn enabled_items(&self) -> Vec<ItemSwitch> {
+		ItemSwitch::all()
+			.filter(|i| self.is_item_enabled(*i))
+			.collect()
+	}
+
+	/// Sets `flag` for the player at `port`. Returns `false` if there's no such player.
+	pub fn set_player_flag(&mut self, port: Port, flag: PlayerFlag, value: bool) -> bool {
+		let player = match self.players.iter_mut().find(|p| p.port == port) {
+			Some(p) => p,
+			None => return false,
+		};
+		set_bit(&mut player.bitfield, flag as u8, value);
+		let b = player.bitfield;
+		let offset = PLAYER_BITFIELD_OFFSET + PLAYER_BLOCK_SIZE * port as usize;
+		if let Some(raw) = self.bytes.0.get_mut(offset) {
+			*raw = b;
+		}
+		true
+	}
+
+	fn set_bitfield(&mut self, idx: usize, value: u8) {
+		self.bitfield[idx] = value;
+		if let Some(raw) = self.bytes.0.get_mut(BITFIELD_OFFSET + idx) {
+			*raw = value;
+		}
+	}
+
+	fn set_bitfield_bit(&mut self, idx: usize, mask: u8, value: bool) {
+		let mut b = self.bitfield[idx];
+		set_bit(&mut b, mask, value);
+		self.set_bitfield(idx, b);
+	}
+}
+
+impl Player {
+	pub fn flag(&self, flag: PlayerFlag) -> bool {
+		self.bitfield & flag as u8 != 0
+	}
+
+	pub fn is_metal(&self) -> bool {
+		self.flag(PlayerFlag::Metal)
+	}
+
+	pub fn is_stamina(&self) -> bool {
+		self.flag(PlayerFlag::Stamina)
+	}
+
+	pub fn is_low_gravity(&self) -> bool {
+		self.flag(PlayerFlag::LowGravity)
+	}
+
+	pub fn is_invisible(&self) -> bool {
+		self.flag(PlayerFlag::Invisible)
+	}
+
+	pub fn is_rumble(&self) -> bool {
+		self.flag(PlayerFlag::Rumble)
+	}
+}
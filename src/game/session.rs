@@ -0,0 +1,151 @@
+//! What kind of session a game was played in, and where.
+//!
+//! Derived from the game's [`Scene`](crate::game::Scene) (added: v3.7), its
+//! [`Match`](crate::game::Match) ID (added: v3.14), netplay info, and the `playedOn` metadata
+//! field.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::game::{Game, Start};
+
+/// Major scene for offline VS mode.
+pub const MAJOR_SCENE_VS: u8 = 0x02;
+
+/// Major scene for Slippi Online (repurposed by Slippi).
+pub const MAJOR_SCENE_ONLINE: u8 = 0x08;
+
+/// Slippi Online mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OnlineMode {
+	Ranked,
+	Unranked,
+	Direct,
+	Teams,
+	/// Online, but the mode wasn't recorded (before v3.14)
+	Unknown,
+}
+
+/// The kind of session a game was played in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Mode {
+	/// Local VS mode
+	Offline,
+	/// Slippi Online
+	Online(OnlineMode),
+	/// Some other scene (e.g. single-player modes), identified by its major scene number
+	Other(u8),
+	/// Not enough information to tell (typically before v3.7)
+	Unknown,
+}
+
+/// Where the game was recorded, according to the `playedOn` metadata field.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Platform {
+	/// Slippi's Dolphin build
+	Dolphin,
+	/// Mainline (upstream) Dolphin
+	MainlineDolphin,
+	/// A console running Nintendont, recorded locally
+	Nintendont,
+	/// A console, recorded remotely over the network (e.g. by a spectating Slippi Launcher)
+	Network,
+	/// Any other `playedOn` value
+	Other(String),
+}
+
+impl Platform {
+	pub fn parse(s: &str) -> Self {
+		match s {
+			"dolphin" => Platform::Dolphin,
+			"mainline dolphin" => Platform::MainlineDolphin,
+			"nintendont" => Platform::Nintendont,
+			"network" => Platform::Network,
+			s => Platform::Other(s.to_string()),
+		}
+	}
+
+	/// Whether the game was played on a console (as opposed to an emulator).
+	pub fn is_console(&self) -> bool {
+		matches!(self, Platform::Nintendont | Platform::Network)
+	}
+
+	/// Whether the replay was streamed from the console over the network (e.g. to Slippi
+	/// Launcher), rather than written by whatever ran the game. This is all `playedOn` tells us;
+	/// it doesn't distinguish the player's own machine from a third-party spectator.
+	pub fn is_network(&self) -> bool {
+		matches!(self, Platform::Network)
+	}
+}
+
+/// Session type & platform.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Session {
+	pub mode: Mode,
+
+	/// `None` if the replay has no `playedOn` metadata
+	pub platform: Option<Platform>,
+}
+
+impl Session {
+	pub fn is_online(&self) -> bool {
+		matches!(self.mode, Mode::Online(_))
+	}
+}
+
+fn online_mode(start: &Start) -> OnlineMode {
+	let id = start.r#match.as_ref().map_or("", |m| m.id.as_str());
+	if id.starts_with("mode.ranked") {
+		OnlineMode::Ranked
+	} else if id.starts_with("mode.unranked") {
+		OnlineMode::Unranked
+	} else if id.starts_with("mode.direct") {
+		OnlineMode::Direct
+	} else if id.starts_with("mode.teams") {
+		OnlineMode::Teams
+	} else {
+		OnlineMode::Unknown
+	}
+}
+
+/// Older online replays have no scene info, but still record connect codes in the metadata.
+fn has_metadata_codes(metadata: Option<&Map<String, Value>>) -> bool {
+	metadata
+		.and_then(|m| m.get("players"))
+		.and_then(|p| p.as_object())
+		.is_some_and(|players| {
+			players.values().any(|p| {
+				p.pointer("/names/code")
+					.and_then(|c| c.as_str())
+					.is_some_and(|c| !c.is_empty())
+			})
+		})
+}
+
+pub(crate) fn session<G: Game + ?Sized>(game: &G) -> Session {
+	let start = game.start();
+	let metadata = game.metadata().as_ref();
+
+	let has_netplay_codes = start.players.iter().any(|p| {
+		p.netplay
+			.as_ref()
+			.is_some_and(|n| !n.code.as_str().is_empty())
+	});
+
+	let mode = match start.scene.map(|s| s.major) {
+		Some(MAJOR_SCENE_ONLINE) => Mode::Online(online_mode(start)),
+		Some(MAJOR_SCENE_VS) => Mode::Offline,
+		Some(major) => Mode::Other(major),
+		None if has_netplay_codes || has_metadata_codes(metadata) => {
+			Mode::Online(OnlineMode::Unknown)
+		}
+		None => Mode::Unknown,
+	};
+
+	let platform = metadata
+		.and_then(|m| m.get("playedOn"))
+		.and_then(|p| p.as_str())
+		.map(Platform::parse);
+
+	Session { mode, platform }
+}
@@ -0,0 +1,231 @@
+//! Grouping games into sets.
+//!
+//! Since v3.14, each game carries a [`Match`](crate::game::Match) ID and game number, which
+//! identify the set it belongs to. Older replays (and offline games, whose match ID is empty) are
+//! grouped heuristically instead: consecutive games between the same players belong to the same
+//! set if each one starts soon after the previous one ends.
+
+use std::collections::HashMap;
+
+use crate::game::{identity::ConnectCode, Game, Port};
+
+/// Options for grouping games into sets.
+#[derive(Clone, Debug)]
+pub struct Opts {
+	/// Maximum time (in seconds) between the end of a game and the start of the next one,
+	/// for games without match info to be considered part of the same set.
+	pub max_gap: u64,
+}
+
+impl Default for Opts {
+	fn default() -> Self {
+		Self { max_gap: 600 }
+	}
+}
+
+/// How we tell players apart across games.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PlayerKey {
+	/// Netplay connect code
+	ConnectCode(ConnectCode),
+	/// In-game name tag (normalized)
+	NameTag(String),
+	/// Fallback for anonymous players
+	Port(Port),
+}
+
+/// A single game within a set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SetGame {
+	/// Index of this game in the input slice
+	pub index: usize,
+	/// Game number within the set, if known (added: v3.14)
+	pub number: Option<u32>,
+	/// Tiebreaker number within the set, if known (added: v3.14)
+	pub tiebreaker: Option<u32>,
+	/// Ports of the winning player(s), if the game had a winner
+	pub winners: Option<Vec<Port>>,
+}
+
+/// A group of games played between the same players.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Set {
+	/// Match ID, if known (added: v3.14)
+	pub id: Option<String>,
+	/// Games in the order they were played
+	pub games: Vec<SetGame>,
+	/// Game numbers that are absent from an otherwise-numbered set
+	pub missing: Vec<u32>,
+	/// Number of games won by each player, in descending order
+	pub score: Vec<(PlayerKey, u32)>,
+}
+
+fn player_key<G: Game>(game: &G, port: Port) -> PlayerKey {
+	let player = game.start().players.iter().find(|p| p.port == port);
+	let code = player.and_then(|p| p.connect_code());
+	let tag = player
+		.and_then(|p| p.name_tag.as_ref())
+		.map(|t| t.to_normalized())
+		.filter(|t| !t.is_empty());
+	match (code, tag) {
+		(Some(code), _) => PlayerKey::ConnectCode(code),
+		(_, Some(tag)) => PlayerKey::NameTag(tag),
+		_ => PlayerKey::Port(port),
+	}
+}
+
+fn player_keys<G: Game>(game: &G) -> Vec<PlayerKey> {
+	let mut keys: Vec<_> = game
+		.start()
+		.players
+		.iter()
+		.map(|p| player_key(game, p.port))
+		.collect();
+	keys.sort();
+	keys
+}
+
+fn match_id<G: Game>(game: &G) -> Option<&str> {
+	game.start()
+		.r#match
+		.as_ref()
+		.map(|m| m.id.as_str())
+		.filter(|id| !id.is_empty())
+}
+
+/// Parses a `startAt` timestamp (e.g. "2018-06-22T07:52:59Z") into seconds since the Unix epoch.
+/// Timestamps lacking a time zone are assumed to be UTC.
+pub(crate) fn parse_timestamp(s: &str) -> Option<i64> {
+	let b = s.as_bytes();
+	if b.len() < 19 || b[4] != b'-' || b[7] != b'-' || b[10] != b'T' {
+		return None;
+	}
+	let num = |range: std::ops::Range<usize>| s.get(range)?.parse::<i64>().ok();
+	let (y, m, d) = (num(0..4)?, num(5..7)?, num(8..10)?);
+	let (hh, mm, ss) = (num(11..13)?, num(14..16)?, num(17..19)?);
+
+	// days since the epoch, from Howard Hinnant's `days_from_civil`
+	let y = if m <= 2 { y - 1 } else { y };
+	let era = y.div_euclid(400);
+	let yoe = y - era * 400;
+	let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+	let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+	let days = era * 146097 + doe - 719468;
+
+	Some(days * 86400 + hh * 3600 + mm * 60 + ss)
+}
+
+fn start_time<G: Game>(game: &G) -> Option<i64> {
+	game.metadata()
+		.as_ref()
+		.and_then(|m| m.get("startAt"))
+		.and_then(|t| t.as_str())
+		.and_then(parse_timestamp)
+}
+
+fn end_time<G: Game>(game: &G) -> Option<i64> {
+	start_time(game).map(|t| t + (game.len() / 60) as i64)
+}
+
+fn set_game<G: Game>(games: &[G], index: usize) -> SetGame {
+	let game = &games[index];
+	let m = game.start().r#match.as_ref();
+	SetGame {
+		index,
+		number: m.map(|m| m.game).filter(|n| *n > 0),
+		tiebreaker: m.map(|m| m.tiebreaker).filter(|_| match_id(game).is_some()),
+		winners: Some(game.outcome().winners).filter(|w| !w.is_empty()),
+	}
+}
+
+fn finish<G: Game>(games: &[G], id: Option<String>, set_games: Vec<SetGame>) -> Set {
+	let mut numbers: Vec<_> = set_games.iter().filter_map(|g| g.number).collect();
+	numbers.sort();
+	numbers.dedup();
+	let missing = match numbers.last() {
+		Some(max) => (1..*max)
+			.filter(|n| numbers.binary_search(n).is_err())
+			.collect(),
+		None => vec![],
+	};
+
+	let mut wins: HashMap<PlayerKey, u32> = HashMap::new();
+	for g in &set_games {
+		let game = &games[g.index];
+		for p in &game.start().players {
+			wins.entry(player_key(game, p.port)).or_default();
+		}
+		for port in g.winners.iter().flatten() {
+			*wins.entry(player_key(game, *port)).or_default() += 1;
+		}
+	}
+	let mut score: Vec<_> = wins.into_iter().collect();
+	score.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+	Set {
+		id,
+		games: set_games,
+		missing,
+		score,
+	}
+}
+
+/// Groups `games` into sets, ordering the games within each set.
+///
+/// Sets are returned in order of their first game's appearance in `games`.
+pub fn sets<G: Game>(games: &[G], opts: Option<&Opts>) -> Vec<Set> {
+	let max_gap = opts.map_or(Opts::default().max_gap, |o| o.max_gap) as i64;
+
+	// (first index, set) pairs, so we can restore input order at the end
+	let mut result: Vec<(usize, Set)> = vec![];
+
+	// games with match info
+	let mut by_id: HashMap<&str, Vec<usize>> = HashMap::new();
+	let mut ids: Vec<&str> = vec![];
+	// games without match info
+	let mut rest: Vec<usize> = vec![];
+	for (idx, game) in games.iter().enumerate() {
+		match match_id(game) {
+			Some(id) => {
+				if !by_id.contains_key(id) {
+					ids.push(id);
+				}
+				by_id.entry(id).or_default().push(idx);
+			}
+			None => rest.push(idx),
+		}
+	}
+
+	for id in ids {
+		let indexes = &by_id[id];
+		let mut set_games: Vec<_> = indexes.iter().map(|&i| set_game(games, i)).collect();
+		set_games.sort_by_key(|g| (g.number, g.tiebreaker, start_time(&games[g.index]), g.index));
+		result.push((indexes[0], finish(games, Some(id.to_string()), set_games)));
+	}
+
+	// stable sort, so games without timestamps keep their relative order
+	rest.sort_by_key(|&i| start_time(&games[i]));
+	let mut groups: Vec<Vec<usize>> = vec![];
+	for idx in rest {
+		let prev = groups.last().and_then(|g| g.last()).map(|&i| &games[i]);
+		let continues = prev.is_some_and(|prev| {
+			player_keys(prev) == player_keys(&games[idx])
+				&& match (end_time(prev), start_time(&games[idx])) {
+					(Some(end), Some(start)) => start <= end + max_gap,
+					_ => false,
+				}
+		});
+		match continues {
+			true => groups.last_mut().unwrap().push(idx),
+			false => groups.push(vec![idx]),
+		}
+	}
+	for group in groups {
+		let first = *group.iter().min().unwrap();
+		let set_games = group.into_iter().map(|i| set_game(games, i)).collect();
+		result.push((first, finish(games, None, set_games)));
+	}
+
+	result.sort_by_key(|(first, _)| *first);
+	result.into_iter().map(|(_, set)| set).collect()
+}
@@ -1,7 +1,13 @@
 //! Melee's internal string encoding.
+//!
+//! Name tags are further limited to the characters Melee's font can show: ASCII letters, digits &
+//! space (stored as single bytes), plus Shift JIS punctuation, full-width alphanumerics, hiragana,
+//! and katakana. See [glyph] & [MeleeString::to_display].
+//!
+//! Generic Shift JIS decoding follows Windows' code page 932, which disagrees with Melee's font for
+//! a few symbols; [glyph] corrects these.
 
 use encoding_rs::SHIFT_JIS;
-use log::warn;
 use serde::{Deserialize, Serialize};
 
 use crate::io::{err, Error, Result};
@@ -32,13 +38,152 @@ impl MeleeString {
 		self.0.clone().chars().map(fix_char).collect::<String>()
 	}
 
-	pub fn bytes(&self) -> std::borrow::Cow<[u8]> {
+	/// Encodes the string as Shift JIS.
+	/// Returns an error if it contains characters that Shift JIS can't represent.
+	pub fn bytes(&self) -> Result<std::borrow::Cow<'_, [u8]>> {
 		let (result, encoding, unmapped) = SHIFT_JIS.encode(&self.0);
 		assert_eq!(encoding, SHIFT_JIS);
 		if unmapped {
-			warn!("unsupported chars in MeleeString: {}", self.0);
+			return Err(err!("unsupported chars in MeleeString: {}", self.0));
 		}
-		result
+		Ok(result)
+	}
+
+	/// Renders the string as Melee would display it, with normalization as in
+	/// [to_normalized](crate::game::shift_jis::MeleeString::to_normalized). Characters that
+	/// Melee's font can't show are replaced with U+FFFD.
+	pub fn to_display(&self) -> String {
+		self.0
+			.chars()
+			.map(|c| match code(c).and_then(glyph) {
+				Some(g) => g,
+				None => char::REPLACEMENT_CHARACTER,
+			})
+			.collect()
+	}
+
+	/// Encodes the string as a name tag.
+	/// Returns an error if it contains characters that Melee's font can't show.
+	pub fn name_tag_bytes(&self) -> Result<Vec<u8>> {
+		let mut bytes = vec![];
+		for c in self.0.chars() {
+			match code(c).filter(|x| glyph(*x).is_some()) {
+				Some(x) if x < 0x100 => bytes.push(x as u8),
+				Some(x) => bytes.extend(x.to_be_bytes()),
+				None => return Err(err!("character not supported by Melee: {:?}", c)),
+			}
+		}
+		Ok(bytes)
+	}
+
+	/// Converts a display string (e.g. from [to_display](Self::to_display)) into the form Melee
+	/// uses for name tags: ASCII letters, digits & spaces as-is, other ASCII symbols as their
+	/// full-width equivalents, and half-width katakana as full-width. Returns an error for
+	/// characters Melee's font can't show.
+	pub fn from_display(s: &str) -> Result<MeleeString> {
+		let s: String = fullwidth_kana(s)
+			.chars()
+			.map(|c| match c {
+				' ' => c,
+				c if c.is_ascii_alphanumeric() => c,
+				'!'..='~' => char::try_from(u32::from(c) - 0x0020 + 0xff00).unwrap(),
+				c => c,
+			})
+			.collect();
+		let s = MeleeString(s);
+		s.name_tag_bytes()?;
+		Ok(s)
+	}
+}
+
+/// Melee's glyphs for codes where they differ from code page 932 (after normalization): the
+/// full-width currency signs & macron (which [to_normalized](MeleeString::to_normalized) doesn't
+/// cover), and JIS X 0208's dash & double vertical line.
+const MELEE_GLYPHS: [(u16, char); 6] = [
+	(0x8150, '¯'),
+	(0x815C, '—'),
+	(0x8161, '‖'),
+	(0x818F, '¥'),
+	(0x8191, '¢'),
+	(0x8192, '£'),
+];
+
+/// Full-width equivalents of the half-width katakana & punctuation from U+FF61 to U+FF9F.
+const HALFWIDTH_KANA: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+
+/// Replaces half-width katakana with full-width, combining (semi-)voiced sound marks with the
+/// preceding kana where possible (e.g. `ｶﾞ` becomes `ガ`).
+fn fullwidth_kana(s: &str) -> String {
+	let mut result = String::new();
+	for c in s.chars() {
+		let Some(k) = u32::from(c)
+			.checked_sub(0xFF61)
+			.and_then(|i| HALFWIDTH_KANA.chars().nth(i as usize))
+		else {
+			result.push(c);
+			continue;
+		};
+		let combined = match (k, result.chars().last()) {
+			('゛', Some('ウ')) => Some('ヴ'),
+			('゛', Some(p)) if "カキクケコサシスセソタチツテトハヒフヘホ".contains(p) => {
+				char::from_u32(u32::from(p) + 1)
+			}
+			('゜', Some(p)) if "ハヒフヘホ".contains(p) => char::from_u32(u32::from(p) + 2),
+			_ => None,
+		};
+		if let Some(c) = combined {
+			result.pop();
+			result.push(c);
+		} else {
+			result.push(k);
+		}
+	}
+	result
+}
+
+/// Shift JIS code for `c` (single-byte codes are < 0x100), if it has one.
+fn code(c: char) -> Option<u16> {
+	if let Some((x, _)) = MELEE_GLYPHS.iter().find(|(_, g)| *g == c) {
+		return Some(*x);
+	}
+	let mut buf = [0; 4];
+	let (bytes, _, unmapped) = SHIFT_JIS.encode(c.encode_utf8(&mut buf));
+	match (unmapped, &*bytes) {
+		(false, [b]) => Some(*b as u16),
+		(false, [hi, lo]) => Some(u16::from_be_bytes([*hi, *lo])),
+		_ => None,
+	}
+}
+
+/// The character Melee's font displays for Shift JIS code `code` (single-byte codes are
+/// < 0x100), normalized as in [to_normalized](crate::game::shift_jis::MeleeString::to_normalized).
+/// Returns `None` if Melee can't display it.
+pub fn glyph(code: u16) -> Option<char> {
+	let supported = match code {
+		0x20 | 0x30..=0x39 | 0x41..=0x5A | 0x61..=0x7A => true, // ASCII space & alphanumerics
+		0x8140..=0x81AC => true,                                // punctuation & symbols
+		0x824F..=0x8258 | 0x8260..=0x8279 | 0x8281..=0x829A => true, // full-width alphanumerics
+		0x829F..=0x82F1 => true,                                // hiragana
+		0x8340..=0x8396 => code != 0x837F,                      // katakana
+		_ => false,
+	};
+	if !supported {
+		return None;
+	}
+	if let Some((_, g)) = MELEE_GLYPHS.iter().find(|(x, _)| *x == code) {
+		return Some(*g);
+	}
+	let bytes = code.to_be_bytes();
+	let bytes = if code < 0x100 {
+		&bytes[1..]
+	} else {
+		&bytes[..]
+	};
+	let decoded = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(bytes)?;
+	let mut chars = decoded.chars();
+	match (chars.next(), chars.next()) {
+		(Some(c), None) => Some(fix_char(c)),
+		_ => None,
 	}
 }
 
@@ -0,0 +1,404 @@
+//! Parallel reading of many replays, for building datasets.
+//!
+//! [`for_each`] reads `.slp` & `.slpp` files on a pool of threads, handing each game to a
+//! callback in the same order as the input paths. At most [`Opts::max_pending`] games are held in
+//! memory at once, however many paths there are.
+//!
+//! [`write_summaries`] & [`write_frames`] build on it to stream a whole directory of replays into
+//! Arrow IPC files: either one row per game, or every game's frames tagged with a `game` column
+//! (the game's index in `paths`).
+
+use std::{
+	collections::BTreeMap,
+	fs::File,
+	io::{BufReader, Write},
+	path::{Path, PathBuf},
+	sync::{mpsc, Condvar, Mutex},
+	thread,
+};
+
+use arrow2::{
+	array::{Array, BooleanArray, PrimitiveArray, Utf8Array},
+	chunk::Chunk,
+	datatypes::{DataType, Field, Schema},
+	io::ipc::write::{Compression, FileWriter, WriteOptions},
+};
+use log::{debug, warn};
+
+use crate::{
+	game::{immutable::Game, port_occupancy, NUM_PORTS},
+	io::{peppi, slippi, Error, Result},
+};
+
+/// Options for batch reading.
+#[derive(Clone, Debug)]
+pub struct Opts {
+	/// Number of replays to parse concurrently.
+	pub threads: usize,
+	/// Maximum number of parsed games held in memory at once (at least `threads`).
+	pub max_pending: usize,
+	/// Options for parsing `.slp` replays.
+	pub slippi: slippi::de::Opts,
+	/// Options for parsing `.slpp` replays.
+	pub peppi: peppi::de::Opts,
+	/// Compression to use for the output Arrow IPC file, if any.
+	pub compression: Option<Compression>,
+	/// Number of games per record batch, when writing summaries.
+	pub batch_size: usize,
+}
+
+impl Default for Opts {
+	fn default() -> Self {
+		let threads = thread::available_parallelism().map_or(1, |n| n.get());
+		Self {
+			threads,
+			max_pending: threads * 2,
+			slippi: Default::default(),
+			peppi: Default::default(),
+			compression: None,
+			batch_size: 1024,
+		}
+	}
+}
+
+/// The result of writing a batch of games.
+#[derive(Debug, Default)]
+pub struct Report {
+	/// Number of games written.
+	pub games: usize,
+	/// Games that couldn't be read or written.
+	pub failed: Vec<(PathBuf, Error)>,
+}
+
+/// Reads a `.slp` or `.slpp` replay, depending on its extension.
+pub fn read_path(path: &Path, opts: Option<&Opts>) -> Result<Game> {
+	let r = BufReader::new(File::open(path)?);
+	match path.extension().and_then(|e| e.to_str()) {
+		Some("slp") => slippi::read(r, opts.map(|o| &o.slippi)),
+		Some("slpp") => peppi::read(r, opts.map(|o| &o.peppi)),
+		_ => Err(err!("unknown replay type: {}", path.display())),
+	}
+}
+
+/// Which indexes have been handed out to workers, and which have been delivered.
+struct Window {
+	next: usize,
+	done: usize,
+	stop: bool,
+}
+
+/// Reads `paths` in parallel, calling `f` with each game's index, path, and parse result, in
+/// order. Stops early if `f` returns an error.
+pub fn for_each<P, F>(paths: &[P], opts: Option<&Opts>, mut f: F) -> Result<()>
+where
+	P: AsRef<Path> + Sync,
+	F: FnMut(usize, &Path, Result<Game>) -> Result<()>,
+{
+	let opts = opts.cloned().unwrap_or_default();
+	let threads = opts.threads.clamp(1, paths.len().max(1));
+	let max_pending = opts.max_pending.max(threads);
+	let window = (
+		Mutex::new(Window {
+			next: 0,
+			done: 0,
+			stop: false,
+		}),
+		Condvar::new(),
+	);
+	let (tx, rx) = mpsc::channel();
+
+	thread::scope(|s| {
+		for _ in 0..threads {
+			let (tx, window, opts) = (tx.clone(), &window, &opts);
+			s.spawn(move || loop {
+				let idx = {
+					let (lock, cvar) = window;
+					let mut w = cvar
+						.wait_while(lock.lock().unwrap(), |w| {
+							!w.stop && w.next < paths.len() && w.next - w.done >= max_pending
+						})
+						.unwrap();
+					if w.stop || w.next >= paths.len() {
+						return;
+					}
+					w.next += 1;
+					w.next - 1
+				};
+				let path = paths[idx].as_ref();
+				debug!("Reading {}", path.display());
+				if tx.send((idx, read_path(path, Some(opts)))).is_err() {
+					return;
+				}
+			});
+		}
+		drop(tx);
+
+		// results arrive in any order, so hold them until it's their turn
+		let mut pending = BTreeMap::new();
+		let mut next = 0;
+		for (idx, game) in rx.iter() {
+			pending.insert(idx, game);
+			while let Some(game) = pending.remove(&next) {
+				let result = f(next, paths[next].as_ref(), game);
+				next += 1;
+				let (lock, cvar) = &window;
+				let mut w = lock.lock().unwrap();
+				w.done = next;
+				w.stop = result.is_err();
+				cvar.notify_all();
+				result?;
+			}
+		}
+		Ok(())
+	})
+}
+
+fn write_options(opts: Option<&Opts>) -> WriteOptions {
+	WriteOptions {
+		compression: opts.and_then(|o| o.compression),
+	}
+}
+
+pub(crate) fn summary_schema() -> Schema {
+	let mut fields = vec![
+		Field::new("game", DataType::UInt32, false),
+		Field::new("path", DataType::Utf8, false),
+		Field::new("hash", DataType::Utf8, true),
+		Field::new("slippi_version", DataType::Utf8, false),
+		Field::new("start_at", DataType::Utf8, true),
+		Field::new("played_on", DataType::Utf8, true),
+		Field::new("last_frame", DataType::Int32, true),
+		Field::new("stage", DataType::UInt16, false),
+		Field::new("is_teams", DataType::Boolean, false),
+		Field::new("end_method", DataType::UInt8, true),
+		Field::new("lras_initiator", DataType::UInt8, true),
+	];
+	for p in 1..=NUM_PORTS {
+		fields.push(Field::new(
+			format!("p{}_character", p),
+			DataType::UInt8,
+			true,
+		));
+		fields.push(Field::new(format!("p{}_code", p), DataType::Utf8, true));
+	}
+	fields.push(Field::new("start", DataType::Utf8, false));
+	fields.push(Field::new("end", DataType::Utf8, true));
+	fields.push(Field::new("metadata", DataType::Utf8, true));
+	Schema::from(fields)
+}
+
+/// Summary columns for games not yet written.
+#[derive(Default)]
+pub(crate) struct Summaries {
+	game: Vec<u32>,
+	path: Vec<String>,
+	hash: Vec<Option<String>>,
+	slippi_version: Vec<String>,
+	start_at: Vec<Option<String>>,
+	played_on: Vec<Option<String>>,
+	last_frame: Vec<Option<i32>>,
+	stage: Vec<u16>,
+	is_teams: Vec<bool>,
+	end_method: Vec<Option<u8>>,
+	lras_initiator: Vec<Option<u8>>,
+	character: [Vec<Option<u8>>; NUM_PORTS],
+	code: [Vec<Option<String>>; NUM_PORTS],
+	start: Vec<String>,
+	end: Vec<Option<String>>,
+	metadata: Vec<Option<String>>,
+}
+
+impl Summaries {
+	pub(crate) fn len(&self) -> usize {
+		self.game.len()
+	}
+
+	pub(crate) fn push(&mut self, idx: usize, path: &Path, game: &Game) -> Result<()> {
+		let metadata_str = |key: &str| {
+			game.metadata
+				.as_ref()
+				.and_then(|m| m.get(key))
+				.and_then(|v| v.as_str())
+				.map(|s| s.to_string())
+		};
+		// fallible conversions first, so that an error leaves all columns the same length
+		let start = serde_json::to_string(&game.start)?;
+		let end = game.end.as_ref().map(serde_json::to_string).transpose()?;
+		let metadata = game
+			.metadata
+			.as_ref()
+			.map(serde_json::to_string)
+			.transpose()?;
+
+		self.game.push(idx.try_into().unwrap());
+		self.path.push(path.to_string_lossy().into_owned());
+		self.hash.push(game.hash.clone());
+		self.slippi_version
+			.push(game.start.slippi.version.to_string());
+		self.start_at.push(metadata_str("startAt"));
+		self.played_on.push(metadata_str("playedOn"));
+		self.last_frame.push(
+			game.metadata
+				.as_ref()
+				.and_then(|m| m.get("lastFrame"))
+				.and_then(|v| v.as_i64())
+				.and_then(|f| f.try_into().ok()),
+		);
+		self.stage.push(game.start.stage);
+		self.is_teams.push(game.start.is_teams);
+		self.end_method
+			.push(game.end.as_ref().map(|e| e.method as u8));
+		self.lras_initiator.push(
+			game.end
+				.as_ref()
+				.and_then(|e| e.lras_initiator)
+				.flatten()
+				.map(|p| p as u8),
+		);
+		for p in 0..NUM_PORTS {
+			let player = game.start.players.iter().find(|pl| pl.port as usize == p);
+			self.character[p].push(player.map(|pl| pl.character));
+			self.code[p].push(
+				player
+					.and_then(|pl| pl.netplay.as_ref())
+					.map(|n| n.code.to_normalized()),
+			);
+		}
+		self.start.push(start);
+		self.end.push(end);
+		self.metadata.push(metadata);
+		Ok(())
+	}
+
+	pub(crate) fn take(&mut self) -> Chunk<Box<dyn Array>> {
+		let s = std::mem::take(self);
+		let mut arrays: Vec<Box<dyn Array>> = vec![
+			PrimitiveArray::from_vec(s.game).boxed(),
+			Utf8Array::<i32>::from_slice(s.path).boxed(),
+			Utf8Array::<i32>::from(s.hash).boxed(),
+			Utf8Array::<i32>::from_slice(s.slippi_version).boxed(),
+			Utf8Array::<i32>::from(s.start_at).boxed(),
+			Utf8Array::<i32>::from(s.played_on).boxed(),
+			PrimitiveArray::from(s.last_frame).boxed(),
+			PrimitiveArray::from_vec(s.stage).boxed(),
+			BooleanArray::from_slice(s.is_teams).boxed(),
+			PrimitiveArray::from(s.end_method).boxed(),
+			PrimitiveArray::from(s.lras_initiator).boxed(),
+		];
+		for (character, code) in s.character.into_iter().zip(s.code) {
+			arrays.push(PrimitiveArray::from(character).boxed());
+			arrays.push(Utf8Array::<i32>::from(code).boxed());
+		}
+		arrays.push(Utf8Array::<i32>::from_slice(s.start).boxed());
+		arrays.push(Utf8Array::<i32>::from(s.end).boxed());
+		arrays.push(Utf8Array::<i32>::from(s.metadata).boxed());
+		Chunk::new(arrays)
+	}
+}
+
+/// Writes one row per game to `w` as an Arrow IPC file, from each game's Game Start, Game End,
+/// metadata, and hash (if computed). Frame data is skipped, regardless of `opts`.
+///
+/// Columns include the game's index in `paths`, its path, some commonly-needed fields, and the
+/// full Game Start, Game End & metadata as JSON.
+pub fn write_summaries<W: Write, P: AsRef<Path> + Sync>(
+	w: W,
+	paths: &[P],
+	opts: Option<&Opts>,
+) -> Result<Report> {
+	let mut opts = opts.cloned().unwrap_or_default();
+	opts.slippi.skip_frames = true;
+	opts.peppi.skip_frames = true;
+
+	let mut writer = FileWriter::try_new(w, summary_schema(), None, write_options(Some(&opts)))?;
+	let mut report = Report::default();
+	let mut summaries = Summaries::default();
+	for_each(paths, Some(&opts), |idx, path, game| {
+		match game.and_then(|game| summaries.push(idx, path, &game)) {
+			Ok(_) => report.games += 1,
+			Err(e) => {
+				warn!("Skipping {}: {}", path.display(), e);
+				report.failed.push((path.to_path_buf(), e));
+			}
+		}
+		if summaries.len() >= opts.batch_size.max(1) {
+			writer.write(&summaries.take(), None)?;
+		}
+		Ok(())
+	})?;
+	if summaries.len() > 0 {
+		writer.write(&summaries.take(), None)?;
+	}
+	writer.finish()?;
+	Ok(report)
+}
+
+/// Writes every game's frames as Arrow IPC files, one record batch per game. Each row has a `game`
+/// column (the game's index in `paths`) and a `frame` column, in the same format as frames in
+/// `.slpp` files.
+///
+/// The frame schema depends on the Slippi version and which ports are occupied, so games are
+/// grouped by schema, with one file per schema. `create` is called to open each file as its
+/// schema is first seen, with the number of files opened so far (so `0` for the first game's
+/// schema). All files stay open until the end. Games that couldn't be read are listed in the
+/// report.
+pub fn write_frames<W, P, F>(mut create: F, paths: &[P], opts: Option<&Opts>) -> Result<Report>
+where
+	W: Write,
+	P: AsRef<Path> + Sync,
+	F: FnMut(usize) -> Result<W>,
+{
+	let mut writers: Vec<(DataType, FileWriter<W>)> = vec![];
+	let mut report = Report::default();
+	for_each(paths, opts, |idx, path, game| {
+		let game = match game {
+			Ok(game) => game,
+			Err(e) => {
+				warn!("Skipping {}: {}", path.display(), e);
+				report.failed.push((path.to_path_buf(), e));
+				return Ok(());
+			}
+		};
+
+		let version = game.start.slippi.version;
+		let ports = port_occupancy(&game.start);
+		let len = game.frames.len();
+		let frames = game.frames.into_struct_array(version, &ports);
+		let writer = match writers.iter().position(|(t, _)| t == frames.data_type()) {
+			Some(n) => &mut writers[n].1,
+			None => {
+				debug!(
+					"New frame schema (file {}): {}",
+					writers.len(),
+					path.display()
+				);
+				let schema = Schema::from(vec![
+					Field::new("game", DataType::UInt32, false),
+					Field::new("frame", frames.data_type().clone(), false),
+				]);
+				let w = create(writers.len())?;
+				let writer = FileWriter::try_new(w, schema, None, write_options(opts))?;
+				writers.push((frames.data_type().clone(), writer));
+				&mut writers.last_mut().unwrap().1
+			}
+		};
+
+		if len > 0 {
+			let id: u32 = idx.try_into().unwrap();
+			writer.write(
+				&Chunk::new(vec![
+					PrimitiveArray::from_vec(vec![id; len]).boxed(),
+					frames.boxed(),
+				]),
+				None,
+			)?;
+		}
+		report.games += 1;
+		Ok(())
+	})?;
+
+	for (_, mut writer) in writers {
+		writer.finish()?;
+	}
+	Ok(report)
+}
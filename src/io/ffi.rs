@@ -0,0 +1,112 @@
+//! Zero-copy sharing of frames & game summaries with other runtimes in the same process (e.g.
+//! pyarrow or DuckDB), via Arrow's
+//! [C Data Interface](https://arrow.apache.org/docs/format/CDataInterface.html) &
+//! [C Stream Interface](https://arrow.apache.org/docs/format/CStreamInterface.html).
+//!
+//! Frames are exported as a single struct array, in the same format as frames in `.slpp` files.
+//! Game summaries are exported as a stream with a single record batch, with the same columns as
+//! [`batch::write_summaries`](super::batch::write_summaries).
+//!
+//! Exported structs own their data until the consumer calls their `release` callback, so hand
+//! them off (e.g. by pointer, to `pyarrow.Array._import_from_c`) rather than dropping them.
+
+use std::path::Path;
+
+use arrow2::{
+	array::{Array, StructArray},
+	datatypes::{DataType, Field},
+	ffi::{self, ArrowArrayStreamReader},
+};
+
+pub use arrow2::ffi::{ArrowArray, ArrowArrayStream, ArrowSchema};
+
+use crate::{
+	frame::{immutable::Frame, mutable::Frame as MutableFrame, PortOccupancy},
+	game::immutable::Game,
+	io::{batch, slippi::Version, Result},
+};
+
+fn frame_type(version: Version, ports: &[PortOccupancy]) -> DataType {
+	Frame::from(MutableFrame::with_capacity(0, version, ports))
+		.into_struct_array(version, ports)
+		.data_type()
+		.clone()
+}
+
+/// Exports frames as a struct array.
+pub fn export_frames(
+	frames: Frame,
+	version: Version,
+	ports: &[PortOccupancy],
+) -> (ArrowSchema, ArrowArray) {
+	let array = frames.into_struct_array(version, ports);
+	let field = Field::new("frame", array.data_type().clone(), false);
+	(
+		ffi::export_field_to_c(&field),
+		ffi::export_array_to_c(array.boxed()),
+	)
+}
+
+/// Imports frames exported by [`export_frames`] (or by another runtime, with the same schema).
+///
+/// Returns an error if the schema doesn't match the one for `version` & `ports`.
+///
+/// # Safety
+///
+/// `schema` & `array` must be valid according to the C Data Interface.
+pub unsafe fn import_frames(
+	schema: &ArrowSchema,
+	array: ArrowArray,
+	version: Version,
+	ports: &[PortOccupancy],
+) -> Result<Frame> {
+	let field = ffi::import_field_from_c(schema)?;
+	if field.data_type != frame_type(version, ports) {
+		return Err(err!("unexpected frame schema: {:?}", field.data_type));
+	}
+	let array = ffi::import_array_from_c(array, field.data_type)?;
+	Ok(Frame::from_struct_array(
+		array
+			.as_any()
+			.downcast_ref::<StructArray>()
+			.ok_or_else(|| err!("expected struct array, got: {:?}", array.data_type()))?
+			.clone(),
+		version,
+	))
+}
+
+/// Exports a summary of each game (one row per game) as a stream. Frame data isn't used.
+pub fn export_summaries<P: AsRef<Path>>(games: &[(P, &Game)]) -> Result<ArrowArrayStream> {
+	let mut summaries = batch::Summaries::default();
+	for (idx, (path, game)) in games.iter().enumerate() {
+		summaries.push(idx, path.as_ref(), game)?;
+	}
+	let data_type = DataType::Struct(batch::summary_schema().fields);
+	let array = StructArray::try_new(data_type.clone(), summaries.take().into_arrays(), None)?;
+	Ok(ffi::export_iterator(
+		Box::new(std::iter::once(Ok(array.boxed()))),
+		Field::new("summary", data_type, false),
+	))
+}
+
+/// Imports a stream of record batches (such as one exported by [`export_summaries`]), as struct
+/// arrays.
+///
+/// # Safety
+///
+/// `stream` must be valid according to the C Stream Interface.
+pub unsafe fn import_stream(stream: Box<ArrowArrayStream>) -> Result<(Field, Vec<StructArray>)> {
+	let mut reader = ArrowArrayStreamReader::try_new(stream)?;
+	let mut batches = vec![];
+	while let Some(array) = reader.next() {
+		let array = array?;
+		batches.push(
+			array
+				.as_any()
+				.downcast_ref::<StructArray>()
+				.ok_or_else(|| err!("expected struct array, got: {:?}", array.data_type()))?
+				.clone(),
+		);
+	}
+	Ok((reader.field().clone(), batches))
+}
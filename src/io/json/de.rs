@@ -0,0 +1,239 @@
+use std::io::Read;
+
+use arrow2::{
+	array::{Array, ListArray, PrimitiveArray, StructArray},
+	bitmap::Bitmap,
+	datatypes::DataType,
+	offset::OffsetsBuffer,
+	types::NativeType,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{
+	frame::{immutable::Frame, mutable::Frame as MutableFrame},
+	game::{self, immutable::Game, port_occupancy, Bytes, Quirks},
+	io::{json::GeckoCodes, slippi, Result},
+};
+
+/// Options for reading JSON games.
+#[derive(Clone, Debug, Default)]
+pub struct Opts {
+	/// Skip all frame data when reading a game for speed
+	/// (when you only need start/end/metadata).
+	pub skip_frames: bool,
+}
+
+/// We only need the raw bytes of Game Start & Game End, since we re-parse them.
+#[derive(Deserialize)]
+struct Raw {
+	bytes: Bytes,
+}
+
+#[derive(Deserialize)]
+struct JsonGame {
+	start: Raw,
+	end: Option<Raw>,
+	metadata: Option<Map<String, Value>>,
+	gecko_codes: Option<GeckoCodes>,
+	hash: Option<String>,
+	quirks: Option<Quirks>,
+	frames: Value,
+}
+
+/// Reads a JSON game (as written by [`write`](super::write)) from `r`.
+pub fn read<R: Read>(r: R, opts: Option<&Opts>) -> Result<Game> {
+	let json: JsonGame = serde_json::from_reader(r)?;
+	let start = slippi::de::game_start(&mut &json.start.bytes.0[..])?;
+	let end = json
+		.end
+		.map(|e| slippi::de::game_end(&mut &e.bytes.0[..]))
+		.transpose()?;
+
+	let version = start.slippi.version;
+	let ports = port_occupancy(&start);
+	let empty = Frame::from(MutableFrame::with_capacity(0, version, &ports));
+	let frames = match opts.is_some_and(|o| o.skip_frames) {
+		true => empty,
+		false => {
+			// the JSON has the same structure as the frames' Arrow representation
+			let data_type = empty.into_struct_array(version, &ports).data_type().clone();
+			let array = match &json.frames {
+				Value::Array(rows) => from_rows(&rows.iter().collect::<Vec<_>>(), &data_type)?,
+				columns => from_columns(columns, &data_type)?,
+			};
+			Frame::from_struct_array(
+				array
+					.as_any()
+					.downcast_ref::<StructArray>()
+					.unwrap()
+					.clone(),
+				version,
+			)
+		}
+	};
+
+	Ok(Game {
+		start,
+		end,
+		metadata: json.metadata,
+		gecko_codes: json.gecko_codes.map(|g| game::GeckoCodes {
+			bytes: g.bytes.0,
+			actual_size: g.actual_size,
+		}),
+		frames,
+		hash: json.hash,
+		quirks: json.quirks,
+	})
+}
+
+fn validity(valid: Vec<bool>) -> Option<Bitmap> {
+	match valid.iter().all(|v| *v) {
+		true => None,
+		false => Some(valid.into()),
+	}
+}
+
+fn from_columns(json: &Value, data_type: &DataType) -> Result<Box<dyn Array>> {
+	Ok(match data_type {
+		DataType::Struct(fields) => {
+			let values = fields
+				.iter()
+				.map(|f| {
+					json.get(&f.name)
+						.ok_or_else(|| err!("missing field: {}", f.name))
+						.and_then(|v| from_columns(v, &f.data_type))
+				})
+				.collect::<Result<_>>()?;
+			let validity = match json.get("validity") {
+				Some(Value::Array(v)) => Some(
+					v.iter()
+						.map(|v| v.as_bool().ok_or_else(|| err!("invalid validity: {}", v)))
+						.collect::<Result<Bitmap>>()?,
+				),
+				Some(v) => return Err(err!("invalid validity: {}", v)),
+				None => None,
+			};
+			StructArray::try_new(data_type.clone(), values, validity)?.boxed()
+		}
+		DataType::List(field) => {
+			let offsets = match json.get("offsets") {
+				Some(Value::Array(o)) => o
+					.iter()
+					.map(|o| {
+						o.as_i64()
+							.and_then(|o| i32::try_from(o).ok())
+							.ok_or_else(|| err!("invalid offset: {}", o))
+					})
+					.collect::<Result<Vec<_>>>()?,
+				_ => return Err(err!("missing offsets")),
+			};
+			let values = json.get("values").ok_or(err!("missing values"))?;
+			ListArray::try_new(
+				data_type.clone(),
+				OffsetsBuffer::try_from(offsets)?,
+				from_columns(values, &field.data_type)?,
+				None,
+			)?
+			.boxed()
+		}
+		_ => match json {
+			Value::Array(values) => primitive(&values.iter().collect::<Vec<_>>(), data_type)?,
+			v => return Err(err!("expected array, got: {}", v)),
+		},
+	})
+}
+
+fn from_rows(rows: &[&Value], data_type: &DataType) -> Result<Box<dyn Array>> {
+	Ok(match data_type {
+		DataType::Struct(fields) => {
+			let values = fields
+				.iter()
+				.map(|f| {
+					let children = rows
+						.iter()
+						.map(|r| match r {
+							Value::Null => Ok(&Value::Null),
+							r => r
+								.get(&f.name)
+								.ok_or_else(|| err!("missing field: {}", f.name)),
+						})
+						.collect::<Result<Vec<_>>>()?;
+					from_rows(&children, &f.data_type)
+				})
+				.collect::<Result<_>>()?;
+			let validity = validity(rows.iter().map(|r| !r.is_null()).collect());
+			StructArray::try_new(data_type.clone(), values, validity)?.boxed()
+		}
+		DataType::List(field) => {
+			let mut offsets = vec![0];
+			let mut children = vec![];
+			for r in rows {
+				match r {
+					Value::Array(values) => children.extend(values),
+					v => return Err(err!("expected array, got: {}", v)),
+				}
+				offsets.push(i32::try_from(children.len()).map_err(|_| err!("too many values"))?);
+			}
+			ListArray::try_new(
+				data_type.clone(),
+				OffsetsBuffer::try_from(offsets)?,
+				from_rows(&children, &field.data_type)?,
+				None,
+			)?
+			.boxed()
+		}
+		_ => primitive(rows, data_type)?,
+	})
+}
+
+fn ints<T>(values: &[&Value]) -> Result<Box<dyn Array>>
+where
+	T: NativeType + TryFrom<i64>,
+{
+	Ok(PrimitiveArray::<T>::from(
+		values
+			.iter()
+			.map(|v| match v {
+				Value::Null => Ok(None),
+				v => v
+					.as_i64()
+					.and_then(|x| T::try_from(x).ok())
+					.map(Some)
+					.ok_or_else(|| err!("invalid {:?}: {}", T::PRIMITIVE, v)),
+			})
+			.collect::<Result<Vec<_>>>()?,
+	)
+	.boxed())
+}
+
+fn floats(values: &[&Value]) -> Result<Box<dyn Array>> {
+	Ok(PrimitiveArray::<f32>::from(
+		values
+			.iter()
+			.map(|v| match v {
+				Value::Null => Ok(None),
+				Value::Number(n) => Ok(n.as_f64().map(|x| x as f32)),
+				Value::String(s) if s == "NaN" => Ok(Some(f32::NAN)),
+				Value::String(s) if s == "inf" => Ok(Some(f32::INFINITY)),
+				Value::String(s) if s == "-inf" => Ok(Some(f32::NEG_INFINITY)),
+				v => Err(err!("invalid float: {}", v)),
+			})
+			.collect::<Result<Vec<_>>>()?,
+	)
+	.boxed())
+}
+
+fn primitive(values: &[&Value], data_type: &DataType) -> Result<Box<dyn Array>> {
+	use DataType::*;
+	match data_type {
+		Int8 => ints::<i8>(values),
+		UInt8 => ints::<u8>(values),
+		Int16 => ints::<i16>(values),
+		UInt16 => ints::<u16>(values),
+		Int32 => ints::<i32>(values),
+		UInt32 => ints::<u32>(values),
+		Float32 => floats(values),
+		t => Err(err!("unsupported data type: {:?}", t)),
+	}
+}
@@ -0,0 +1,34 @@
+//! JSON serialization of entire games, including frame data.
+//!
+//! A game is a single JSON object, with `start`, `end`, `metadata`, `gecko_codes`, `hash`, and
+//! `quirks` keys holding the corresponding [`Game`](crate::game::immutable::Game) fields, and a
+//! `frames` key holding the frame data in one of two layouts (see [`ser::Frames`]):
+//!
+//! - columns: an object with the same nested structure as
+//!   [`Frame::into_struct_array`](crate::frame::immutable::Frame::into_struct_array), whose leaves
+//!   are arrays of values (one per frame). Structs with nulls (e.g. Nana, on frames where she's
+//!   absent) also have a `validity` array, and per-frame lists (items & stage data) are objects
+//!   with `offsets` and `values`.
+//! - rows: an array of frame objects, with the same nested structure. Absent data is `null`.
+//!
+//! Floats that JSON can't represent (NaN & infinities) are written as the strings `"NaN"`,
+//! `"inf"`, and `"-inf"`.
+//!
+//! As with `.slpp`, the raw bytes of the Game Start & Game End events (`start.bytes` &
+//! `end.bytes`) are authoritative when reading, so games round-trip to identical `.slp` files.
+
+pub mod de;
+pub mod ser;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Bytes;
+
+pub use de::read;
+pub use ser::write;
+
+#[derive(Serialize, Deserialize)]
+struct GeckoCodes {
+	actual_size: u32,
+	bytes: Bytes,
+}
@@ -0,0 +1,175 @@
+use std::io::Write;
+
+use arrow2::{
+	array::{Array, ListArray, PrimitiveArray, StructArray},
+	datatypes::DataType,
+	types::NativeType,
+};
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::{
+	game::{immutable::Game, port_occupancy, Bytes, End, Quirks, Start},
+	io::{json::GeckoCodes, Result},
+};
+
+/// Layout of frame data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Frames {
+	/// One array per field (compact, and fast to load into dataframes).
+	#[default]
+	Columns,
+	/// One object per frame (easier to read).
+	Rows,
+}
+
+/// Options for writing JSON games.
+#[derive(Clone, Debug, Default)]
+pub struct Opts {
+	pub frames: Frames,
+}
+
+#[derive(Serialize)]
+struct JsonGame<'a> {
+	start: &'a Start,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	end: Option<&'a End>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	metadata: Option<&'a Map<String, Value>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	gecko_codes: Option<GeckoCodes>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	hash: Option<&'a String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	quirks: Option<&'a Quirks>,
+	frames: Value,
+}
+
+/// Writes a game to `w` as JSON.
+pub fn write<W: Write>(w: W, game: Game, opts: Option<&Opts>) -> Result<()> {
+	let version = game.start.slippi.version;
+	let ports = port_occupancy(&game.start);
+	let len = game.frames.len();
+	let frames = game.frames.into_struct_array(version, &ports);
+	let frames = match opts.map(|o| o.frames).unwrap_or_default() {
+		Frames::Columns => column(&frames)?,
+		Frames::Rows => Value::Array((0..len).map(|i| row(&frames, i)).collect::<Result<_>>()?),
+	};
+
+	serde_json::to_writer(
+		w,
+		&JsonGame {
+			start: &game.start,
+			end: game.end.as_ref(),
+			metadata: game.metadata.as_ref(),
+			gecko_codes: game.gecko_codes.as_ref().map(|g| GeckoCodes {
+				actual_size: g.actual_size,
+				bytes: Bytes(g.bytes.clone()),
+			}),
+			hash: game.hash.as_ref(),
+			quirks: game.quirks.as_ref(),
+			frames,
+		},
+	)?;
+	Ok(())
+}
+
+fn column(array: &dyn Array) -> Result<Value> {
+	Ok(match array.data_type() {
+		DataType::Struct(fields) => {
+			let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+			let mut map = Map::new();
+			for (field, values) in fields.iter().zip(array.values()) {
+				map.insert(field.name.clone(), column(values.as_ref())?);
+			}
+			if let Some(validity) = array.validity() {
+				map.insert(
+					"validity".to_string(),
+					validity.iter().map(Value::Bool).collect(),
+				);
+			}
+			Value::Object(map)
+		}
+		DataType::List(_) => {
+			let array = array.as_any().downcast_ref::<ListArray<i32>>().unwrap();
+			let mut map = Map::new();
+			map.insert(
+				"offsets".to_string(),
+				array.offsets().iter().map(|o| Value::from(*o)).collect(),
+			);
+			map.insert("values".to_string(), column(array.values().as_ref())?);
+			Value::Object(map)
+		}
+		_ => Value::Array(
+			(0..array.len())
+				.map(|i| value(array, i))
+				.collect::<Result<_>>()?,
+		),
+	})
+}
+
+fn row(array: &dyn Array, i: usize) -> Result<Value> {
+	if array.is_null(i) {
+		return Ok(Value::Null);
+	}
+	Ok(match array.data_type() {
+		DataType::Struct(fields) => {
+			let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+			let mut map = Map::new();
+			for (field, values) in fields.iter().zip(array.values()) {
+				map.insert(field.name.clone(), row(values.as_ref(), i)?);
+			}
+			Value::Object(map)
+		}
+		DataType::List(_) => {
+			let array = array.as_any().downcast_ref::<ListArray<i32>>().unwrap();
+			let (start, end) = array.offsets().start_end(i);
+			Value::Array(
+				(start..end)
+					.map(|j| row(array.values().as_ref(), j))
+					.collect::<Result<_>>()?,
+			)
+		}
+		_ => value(array, i)?,
+	})
+}
+
+fn primitive<T: NativeType + Into<Value>>(array: &dyn Array, i: usize) -> Value {
+	array
+		.as_any()
+		.downcast_ref::<PrimitiveArray<T>>()
+		.unwrap()
+		.value(i)
+		.into()
+}
+
+fn value(array: &dyn Array, i: usize) -> Result<Value> {
+	use DataType::*;
+	if array.is_null(i) {
+		return Ok(Value::Null);
+	}
+	Ok(match array.data_type() {
+		Int8 => primitive::<i8>(array, i),
+		UInt8 => primitive::<u8>(array, i),
+		Int16 => primitive::<i16>(array, i),
+		UInt16 => primitive::<u16>(array, i),
+		Int32 => primitive::<i32>(array, i),
+		UInt32 => primitive::<u32>(array, i),
+		Float32 => {
+			let x = array
+				.as_any()
+				.downcast_ref::<PrimitiveArray<f32>>()
+				.unwrap()
+				.value(i);
+			// go via the shortest decimal representation of the `f32`, so we write `0.1` rather
+			// than `0.10000000149011612`
+			match x.to_string().parse().ok().and_then(Number::from_f64) {
+				Some(n) => Value::Number(n),
+				None if x.is_nan() => Value::from("NaN"),
+				None if x > 0.0 => Value::from("inf"),
+				None => Value::from("-inf"),
+			}
+		}
+		t => return Err(err!("unsupported data type: {:?}", t)),
+	})
+}
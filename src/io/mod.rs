@@ -10,8 +10,15 @@ macro_rules! err {
 
 pub(crate) use err;
 
+pub mod batch;
+pub mod ffi;
+pub mod json;
+pub mod numpy;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod peppi;
 pub mod slippi;
+pub mod tabular;
 pub(crate) mod ubjson;
 
 use std::io::{Read, Seek, SeekFrom};
@@ -36,6 +43,10 @@ pub enum Error {
 
 	#[error("invalid UTF8: {0}")]
 	Utf8(#[from] std::string::FromUtf8Error),
+
+	#[cfg(feature = "parquet")]
+	#[error("invalid Parquet: {0}")]
+	Parquet(#[from] ::parquet::errors::ParquetError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
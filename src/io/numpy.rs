@@ -0,0 +1,208 @@
+//! NumPy (`.npy` & `.npz`) export of frame data, for machine learning.
+//!
+//! [`write_npz`] writes selected fields of each character's frame data as separate `.npy` arrays
+//! (one value per frame), named as in [`flat`] (e.g. `p1_leader_post_position_x`). Load them with
+//! [`numpy.load`](https://numpy.org/doc/stable/reference/generated/numpy.load.html).
+//!
+//! Arrow types map to NumPy dtypes as follows:
+//!
+//! | Arrow     | NumPy |
+//! |-----------|-------|
+//! | `Int8`    | `i1`  |
+//! | `UInt8`   | `u1`  |
+//! | `Int16`   | `<i2` |
+//! | `UInt16`  | `<u2` |
+//! | `Int32`   | `<i4` |
+//! | `UInt32`  | `<u4` |
+//! | `Float32` | `<f4` |
+//! | `Boolean` | `b1`  |
+//!
+//! NumPy arrays can't hold nulls, so null values are written as `NaN` (floats) or `0`
+//! (everything else). Characters that are sometimes absent (i.e. Nana) get an extra boolean
+//! array (e.g. `p1_follower_valid`) that's `false` wherever that's the case.
+
+use std::io::{BufWriter, Seek, Write};
+
+use arrow2::{
+	array::{Array, BooleanArray, PrimitiveArray},
+	datatypes::DataType,
+	types::NativeType,
+};
+use byteorder::{LittleEndian, WriteBytesExt};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+	frame::{flat, Rollbacks},
+	game::{immutable::Game, port_occupancy},
+	io::Result,
+};
+
+const NPY_SIGNATURE: [u8; 8] = *b"\x93NUMPY\x01\x00";
+
+/// Options for NumPy export.
+#[derive(Clone, Debug, Default)]
+pub struct Opts {
+	/// Fields to export, as paths relative to each character's frame data (e.g. `pre.joystick`
+	/// or `post.position.x`). Every field under a path is exported. If empty, all fields are.
+	pub fields: Vec<String>,
+	/// Drop rolled-back frames, keeping only one of each frame ID. See
+	/// [`Frame::rollbacks`](crate::frame::immutable::Frame::rollbacks).
+	pub rollbacks: Option<Rollbacks>,
+	/// Compress the arrays (like `numpy.savez_compressed`).
+	pub compress: bool,
+}
+
+fn descr(data_type: &DataType) -> Result<&'static str> {
+	use DataType::*;
+	Ok(match data_type {
+		Int8 => "|i1",
+		UInt8 => "|u1",
+		Int16 => "<i2",
+		UInt16 => "<u2",
+		Int32 => "<i4",
+		UInt32 => "<u4",
+		Float32 => "<f4",
+		Boolean => "|b1",
+		t => return Err(err!("unsupported data type: {:?}", t)),
+	})
+}
+
+fn write_values<T: NativeType, W: Write>(
+	w: &mut W,
+	array: &dyn Array,
+	skip: Option<&[bool]>,
+	null: T,
+) -> Result<()> {
+	let array = array.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
+	for (i, x) in array.iter().enumerate() {
+		if !skip.is_some_and(|s| s[i]) {
+			w.write_all(x.copied().unwrap_or(null).to_le_bytes().as_ref())?;
+		}
+	}
+	Ok(())
+}
+
+/// Writes `array` as a 1-dimensional `.npy`, omitting rows for which `skip` is `true`.
+fn write_array<W: Write>(mut w: W, array: &dyn Array, skip: Option<&[bool]>) -> Result<()> {
+	let len = skip.map_or(array.len(), |s| s.iter().filter(|s| !**s).count());
+	let mut header = format!(
+		"{{'descr': '{}', 'fortran_order': False, 'shape': ({},), }}",
+		descr(array.data_type())?,
+		len
+	);
+	// NumPy pads the header with spaces (plus a newline) so the data is 64-byte aligned
+	let unpadded = NPY_SIGNATURE.len() + 2 + header.len() + 1;
+	header.extend(std::iter::repeat_n(' ', (64 - unpadded % 64) % 64));
+	header.push('\n');
+	w.write_all(&NPY_SIGNATURE)?;
+	w.write_u16::<LittleEndian>(header.len().try_into().unwrap())?;
+	w.write_all(header.as_bytes())?;
+
+	use DataType::*;
+	match array.data_type() {
+		Int8 => write_values::<i8, _>(&mut w, array, skip, 0),
+		UInt8 => write_values::<u8, _>(&mut w, array, skip, 0),
+		Int16 => write_values::<i16, _>(&mut w, array, skip, 0),
+		UInt16 => write_values::<u16, _>(&mut w, array, skip, 0),
+		Int32 => write_values::<i32, _>(&mut w, array, skip, 0),
+		UInt32 => write_values::<u32, _>(&mut w, array, skip, 0),
+		Float32 => write_values::<f32, _>(&mut w, array, skip, f32::NAN),
+		Boolean => {
+			let array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+			for (i, x) in array.iter().enumerate() {
+				if !skip.is_some_and(|s| s[i]) {
+					w.write_u8(x.unwrap_or(false).into())?;
+				}
+			}
+			Ok(())
+		}
+		t => Err(err!("unsupported data type: {:?}", t)),
+	}
+}
+
+/// Writes a single array in `.npy` format.
+pub fn write_npy<W: Write>(w: W, array: &dyn Array) -> Result<()> {
+	write_array(w, array, None)
+}
+
+/// Writes the selected fields of a game's frame data in `.npz` format, one `.npy` per field
+/// per character, plus the frame IDs (as `id`).
+///
+/// Returns an error if any of [`Opts::fields`] doesn't exist in this game (e.g. because it was
+/// added in a later Slippi version).
+pub fn write_npz<W: Write + Seek>(w: W, game: Game, opts: Option<&Opts>) -> Result<()> {
+	let fields: Vec<Vec<_>> = opts
+		.map(|o| o.fields.iter().map(|f| f.split('.').collect()).collect())
+		.unwrap_or_default();
+	let skip = opts
+		.and_then(|o| o.rollbacks)
+		.map(|keep| game.frames.rollbacks(keep));
+	let skip = skip.as_deref();
+
+	let version = game.start.slippi.version;
+	let ports = port_occupancy(&game.start);
+	let mut arrays = vec![];
+	let mut validities = vec![];
+	let mut found = vec![false; fields.len()];
+	for (path, array) in flat::paths(game.frames, version, &ports) {
+		let name = path.join("_");
+		if name == "id" {
+			arrays.push((name, array));
+			continue;
+		}
+		// per-character fields have paths like `[port, "leader" | "follower", "pre" | "post", ...]`
+		if !ports
+			.iter()
+			.any(|p| p.port.to_string().to_lowercase() == path[0])
+		{
+			continue;
+		}
+		let field = &path[2..];
+		let mut selected = fields.is_empty();
+		for (f, found) in fields.iter().zip(found.iter_mut()) {
+			if f.len() <= field.len() && std::iter::zip(f, field).all(|(a, b)| a == b) {
+				*found = true;
+				selected = true;
+			}
+		}
+		if !selected {
+			continue;
+		}
+		// pre-frame & post-frame fields have separate validities (which can differ, e.g. when
+		// a follower's post-frame event is missing), so a character is only valid where all of
+		// its selected fields are
+		if let Some(validity) = array.validity() {
+			let valid = format!("{}_valid", path[..2].join("_"));
+			match validities.iter_mut().find(|(n, _, _)| *n == valid) {
+				Some((_, _, v)) => *v = &*v & validity,
+				None => validities.push((valid, arrays.len(), validity.clone())),
+			}
+		}
+		arrays.push((name, array));
+	}
+	if let Some(idx) = found.iter().position(|f| !f) {
+		return Err(err!("no such field: {}", opts.unwrap().fields[idx]));
+	}
+	// insert each `_valid` array before its character's first field
+	for (name, idx, validity) in validities.into_iter().rev() {
+		let validity = BooleanArray::new(DataType::Boolean, validity, None);
+		arrays.insert(idx, (name, validity.boxed()));
+	}
+
+	let options = SimpleFileOptions::default()
+		.compression_method(match opts.is_some_and(|o| o.compress) {
+			true => CompressionMethod::Deflated,
+			false => CompressionMethod::Stored,
+		})
+		.large_file(true);
+	let mut zip = ZipWriter::new(w);
+	for (name, array) in arrays {
+		zip.start_file(format!("{}.npy", name), options)
+			.map_err(std::io::Error::from)?;
+		let mut w = BufWriter::new(&mut zip);
+		write_array(&mut w, array.as_ref(), skip)?;
+		w.flush()?;
+	}
+	zip.finish().map_err(std::io::Error::from)?;
+	Ok(())
+}
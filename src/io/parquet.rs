@@ -0,0 +1,180 @@
+//! Parquet export of frame data, for querying with tools like DuckDB or Spark.
+//!
+//! Frames are written one row per frame, with the same nested structure as the frame data in
+//! `.slpp` files (see [`Frame::into_struct_array`](crate::frame::immutable::Frame::into_struct_array)).
+//! Set [`Opts::flatten`] to instead get one column per field (see [`flat`]). Per-frame lists
+//! (items & stage data) stay nested either way, since they have a variable number of entries per
+//! frame.
+//!
+//! Requires the `parquet` feature.
+
+use std::{io::Write, sync::Arc};
+
+use ::parquet::{arrow::ArrowWriter, errors::ParquetError, file::properties::WriterProperties};
+use arrow2::{
+	array::{Array, ListArray, PrimitiveArray, StructArray},
+	bitmap::Bitmap,
+	datatypes::DataType,
+	types::NativeType,
+};
+use arrow_array::{
+	types::{Float32Type, Int16Type, Int32Type, Int8Type, UInt16Type, UInt32Type, UInt8Type},
+	ArrayRef, ArrowPrimitiveType, BooleanArray, RecordBatch,
+};
+use arrow_buffer::{ArrowNativeType, NullBuffer, OffsetBuffer, ScalarBuffer};
+use arrow_schema::{Field, Schema};
+
+pub use ::parquet::basic::{Compression, ZstdLevel};
+
+use crate::{
+	frame::{flat, Rollbacks},
+	game::{immutable::Game, port_occupancy},
+	io::Result,
+};
+
+/// Options for writing Parquet files.
+#[derive(Clone, Debug, Default)]
+pub struct Opts {
+	/// Compression to use for column data, if any.
+	pub compression: Option<Compression>,
+	/// Write one column per field, instead of nested structs. See [`flat`].
+	pub flatten: bool,
+	/// Drop rolled-back frames, keeping only one of each frame ID. See
+	/// [`Frame::rollbacks`](crate::frame::immutable::Frame::rollbacks).
+	pub rollbacks: Option<Rollbacks>,
+}
+
+/// Writes a game's frame data to `w` in Parquet format, one row per frame.
+pub fn write<W: Write + Send>(w: W, game: Game, opts: Option<&Opts>) -> Result<()> {
+	let flatten = opts.is_some_and(|o| o.flatten);
+	let rollbacks = opts
+		.and_then(|o| o.rollbacks)
+		.map(|keep| game.frames.rollbacks(keep));
+
+	let version = game.start.slippi.version;
+	let ports = port_occupancy(&game.start);
+	let arrays: Vec<_> = if flatten {
+		flat::paths(game.frames, version, &ports)
+			.into_iter()
+			.map(|(path, array)| {
+				// per-character paths look like `[port, "leader" | "follower", ...]`
+				let nullable = path.get(1).is_some_and(|p| p == "follower");
+				(path.join("_"), nullable, array)
+			})
+			.collect()
+	} else {
+		let frames = game.frames.into_struct_array(version, &ports);
+		let (fields, values, _) = frames.into_data();
+		fields
+			.into_iter()
+			.zip(values)
+			.map(|(f, array)| (f.name, f.is_nullable, array))
+			.collect()
+	};
+
+	let mut fields = vec![];
+	let mut columns = vec![];
+	for (name, nullable, array) in arrays {
+		let column = convert(array.as_ref())?;
+		fields.push(Field::new(
+			name,
+			column.data_type().clone(),
+			nullable || column.null_count() > 0,
+		));
+		columns.push(column);
+	}
+
+	let mut batch =
+		RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(ParquetError::from)?;
+	if let Some(rollbacks) = rollbacks {
+		let keep: BooleanArray = rollbacks.into_iter().map(|r| Some(!r)).collect();
+		batch =
+			arrow_select::filter::filter_record_batch(&batch, &keep).map_err(ParquetError::from)?;
+	}
+
+	let props = WriterProperties::builder()
+		.set_compression(
+			opts.and_then(|o| o.compression)
+				.unwrap_or(Compression::UNCOMPRESSED),
+		)
+		.build();
+	let mut writer = ArrowWriter::try_new(w, batch.schema(), Some(props))?;
+	writer.write(&batch)?;
+	writer.close()?;
+	Ok(())
+}
+
+/// Whether a field is nullable. The frame schema declares every field non-nullable, but followers
+/// are null wherever Nana is absent, so they're always nullable (even if this game happens to have
+/// no such frames) to keep the schema the same across games. Any other field with nulls is made
+/// nullable rather than rejected.
+fn nullable(field: &arrow2::datatypes::Field, column: &ArrayRef) -> bool {
+	field.is_nullable || field.name == "follower" || column.null_count() > 0
+}
+
+/// Converts an arrow2 array to its arrow-rs equivalent (which the Parquet writer expects).
+fn convert(array: &dyn Array) -> Result<ArrayRef> {
+	use DataType::*;
+	Ok(match array.data_type() {
+		Int8 => primitive::<i8, Int8Type>(array),
+		UInt8 => primitive::<u8, UInt8Type>(array),
+		Int16 => primitive::<i16, Int16Type>(array),
+		UInt16 => primitive::<u16, UInt16Type>(array),
+		Int32 => primitive::<i32, Int32Type>(array),
+		UInt32 => primitive::<u32, UInt32Type>(array),
+		Float32 => primitive::<f32, Float32Type>(array),
+		Struct(children) => {
+			let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+			let mut fields = vec![];
+			let mut columns = vec![];
+			for (child, values) in children.iter().zip(array.values()) {
+				let column = convert(values.as_ref())?;
+				fields.push(Field::new(
+					&child.name,
+					column.data_type().clone(),
+					nullable(child, &column),
+				));
+				columns.push(column);
+			}
+			Arc::new(
+				arrow_array::StructArray::try_new(fields.into(), columns, nulls(array.validity()))
+					.map_err(ParquetError::from)?,
+			)
+		}
+		List(child) => {
+			let array = array.as_any().downcast_ref::<ListArray<i32>>().unwrap();
+			let values = convert(array.values().as_ref())?;
+			let field = Field::new(
+				&child.name,
+				values.data_type().clone(),
+				nullable(child, &values),
+			);
+			Arc::new(
+				arrow_array::ListArray::try_new(
+					Arc::new(field),
+					OffsetBuffer::new(ScalarBuffer::from(array.offsets().buffer().to_vec())),
+					values,
+					nulls(array.validity()),
+				)
+				.map_err(ParquetError::from)?,
+			)
+		}
+		t => return Err(err!("unsupported data type: {:?}", t)),
+	})
+}
+
+fn primitive<T, A>(array: &dyn Array) -> ArrayRef
+where
+	T: NativeType + ArrowNativeType,
+	A: ArrowPrimitiveType<Native = T>,
+{
+	let array = array.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
+	Arc::new(arrow_array::PrimitiveArray::<A>::new(
+		ScalarBuffer::from(array.values().to_vec()),
+		nulls(array.validity()),
+	))
+}
+
+fn nulls(validity: Option<&Bitmap>) -> Option<NullBuffer> {
+	validity.map(|v| NullBuffer::from(v.iter().collect::<Vec<_>>()))
+}
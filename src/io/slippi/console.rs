@@ -0,0 +1,546 @@
+//! Client for the Slippi console communication protocol, used to stream replay data from a Wii
+//! (or Nintendont) running Slippi, or from anything else that speaks the protocol.
+//!
+//! Messages are UBJSON maps with `type` & `payload` keys, each preceded by its length as a
+//! big-endian `u32`. After a handshake, the console sends [`Message::Replay`] messages containing
+//! raw event data (the contents of a replay's `raw` element, for one game after another), plus
+//! periodic [`Message::KeepAlive`]s.
+//!
+//! Every byte of event data has a position (the "cursor"). A client that loses its connection can
+//! [`reconnect`](Connection::reconnect), and the console will resume from where it left off.
+
+use std::{
+	collections::VecDeque,
+	io::{self, Read, Write},
+	net::{SocketAddr, TcpStream, ToSocketAddrs},
+	time::Duration,
+};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use log::{debug, info, warn};
+use serde_json::{Map, Value};
+
+use crate::{
+	game::immutable::Game,
+	io::{
+		slippi::{
+			de::{self, Event, ParseState},
+			live::parse_available,
+		},
+		ubjson, Result,
+	},
+};
+
+/// Port that Slippi consoles listen on.
+pub const DEFAULT_PORT: u16 = 51441;
+
+/// Largest message we'll accept, to guard against garbage lengths.
+const MAX_MESSAGE_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Message types.
+pub mod message_type {
+	pub const HANDSHAKE: u8 = 1;
+	pub const REPLAY: u8 = 2;
+	pub const KEEP_ALIVE: u8 = 3;
+}
+
+/// A protocol message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+	/// Sent by the client when it connects.
+	HandshakeRequest {
+		/// Position to resume from (`0` for a new client).
+		cursor: u64,
+		/// Token from a previous connection's [`HandshakeResponse`](Self::HandshakeResponse)
+		/// (`0` for a new client).
+		client_token: u32,
+		/// Whether the client wants data as soon as possible, rather than in larger batches.
+		is_realtime: bool,
+	},
+	/// The console's reply to a handshake.
+	HandshakeResponse {
+		nick: String,
+		nintendont_version: String,
+		client_token: u32,
+		/// Position the console will send data from next.
+		pos: u64,
+	},
+	/// Raw event data.
+	Replay {
+		/// Position of the first byte of `data`.
+		pos: u64,
+		/// Position of the byte following `data`.
+		next_pos: u64,
+		/// Set if the console couldn't send data from the requested position (e.g. because its
+		/// buffer overflowed), meaning some data was skipped.
+		force_pos: bool,
+		data: Vec<u8>,
+	},
+	/// Sent periodically by the console, so clients can detect dead connections.
+	KeepAlive,
+}
+
+fn get<'a>(m: &'a Map<String, Value>, key: &str) -> Result<&'a Value> {
+	m.get(key)
+		.ok_or_else(|| err!("missing key in console message: {}", key))
+}
+
+fn to_bytes(v: &Value) -> Result<Vec<u8>> {
+	v.as_array()
+		.ok_or_else(|| err!("expected byte array, but got: {}", v))?
+		.iter()
+		.map(|x| {
+			x.as_u64()
+				.and_then(|x| u8::try_from(x).ok())
+				.ok_or_else(|| err!("expected byte, but got: {}", x))
+		})
+		.collect()
+}
+
+fn to_u64(v: &Value) -> Result<u64> {
+	let bytes: [u8; 8] = to_bytes(v)?
+		.try_into()
+		.map_err(|_| err!("expected 8-byte position, but got: {}", v))?;
+	Ok(u64::from_be_bytes(bytes))
+}
+
+fn to_u32(v: &Value) -> Result<u32> {
+	let bytes: [u8; 4] = to_bytes(v)?
+		.try_into()
+		.map_err(|_| err!("expected 4-byte token, but got: {}", v))?;
+	Ok(u32::from_be_bytes(bytes))
+}
+
+fn to_bool(v: &Value) -> Result<bool> {
+	v.as_bool()
+		.ok_or_else(|| err!("expected boolean, but got: {}", v))
+}
+
+fn to_string(v: &Value) -> Result<String> {
+	v.as_str()
+		.map(str::to_string)
+		.ok_or_else(|| err!("expected string, but got: {}", v))
+}
+
+impl Message {
+	/// Reads a message (including its length prefix). Returns `None` if the stream ends before
+	/// the message starts.
+	pub fn read<R: Read>(mut r: R) -> Result<Option<Self>> {
+		let len = match r.read_u32::<BigEndian>() {
+			Ok(len) => len,
+			Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+			Err(e) => return Err(e.into()),
+		};
+		if len > MAX_MESSAGE_SIZE {
+			return Err(err!("console message too large: {}", len));
+		}
+		let mut buf = vec![0; len as usize];
+		r.read_exact(&mut buf)?;
+		Self::decode(&buf).map(Some)
+	}
+
+	/// Decodes a message's UBJSON body (without length prefix).
+	pub fn decode(bytes: &[u8]) -> Result<Self> {
+		let m = match ubjson::read_value(&mut &*bytes)? {
+			Value::Object(m) => m,
+			v => return Err(err!("expected map, but got: {}", v)),
+		};
+		let r#type = get(&m, "type")?
+			.as_u64()
+			.ok_or_else(|| err!("invalid console message type: {}", m["type"]))?;
+		let empty = Map::new();
+		let payload = match m.get("payload") {
+			Some(Value::Object(p)) => p,
+			Some(Value::Null) | None => &empty,
+			Some(p) => return Err(err!("expected payload map, but got: {}", p)),
+		};
+		match u8::try_from(r#type) {
+			Ok(message_type::HANDSHAKE) if payload.contains_key("cursor") => {
+				Ok(Self::HandshakeRequest {
+					cursor: to_u64(get(payload, "cursor")?)?,
+					client_token: to_u32(get(payload, "clientToken")?)?,
+					is_realtime: payload.get("isRealtime").map_or(Ok(false), to_bool)?,
+				})
+			}
+			Ok(message_type::HANDSHAKE) => Ok(Self::HandshakeResponse {
+				nick: payload.get("nick").map_or(Ok(String::new()), to_string)?,
+				nintendont_version: payload
+					.get("nintendontVersion")
+					.map_or(Ok(String::new()), to_string)?,
+				client_token: to_u32(get(payload, "clientToken")?)?,
+				pos: to_u64(get(payload, "pos")?)?,
+			}),
+			Ok(message_type::REPLAY) => Ok(Self::Replay {
+				pos: to_u64(get(payload, "pos")?)?,
+				next_pos: to_u64(get(payload, "nextPos")?)?,
+				force_pos: payload.get("forcePos").map_or(Ok(false), to_bool)?,
+				data: to_bytes(get(payload, "data")?)?,
+			}),
+			Ok(message_type::KEEP_ALIVE) => Ok(Self::KeepAlive),
+			_ => Err(err!("unknown console message type: {}", r#type)),
+		}
+	}
+
+	/// Encodes the message's UBJSON body (without length prefix).
+	pub fn encode(&self) -> Vec<u8> {
+		let mut w = vec![];
+		self.encode_into(&mut w).unwrap();
+		w
+	}
+
+	fn encode_into(&self, w: &mut Vec<u8>) -> io::Result<()> {
+		let r#type = match self {
+			Self::HandshakeRequest { .. } | Self::HandshakeResponse { .. } => {
+				message_type::HANDSHAKE
+			}
+			Self::Replay { .. } => message_type::REPLAY,
+			Self::KeepAlive => message_type::KEEP_ALIVE,
+		};
+		write!(w, "{{")?;
+		ubjson::write_key(w, "type")?;
+		write!(w, "U")?;
+		w.write_u8(r#type)?;
+		ubjson::write_key(w, "payload")?;
+		write!(w, "{{")?;
+		match self {
+			Self::HandshakeRequest {
+				cursor,
+				client_token,
+				is_realtime,
+			} => {
+				ubjson::write_key(w, "cursor")?;
+				ubjson::write_bytes(w, &cursor.to_be_bytes())?;
+				ubjson::write_key(w, "clientToken")?;
+				ubjson::write_bytes(w, &client_token.to_be_bytes())?;
+				ubjson::write_key(w, "isRealtime")?;
+				write!(w, "{}", if *is_realtime { "T" } else { "F" })?;
+			}
+			Self::HandshakeResponse {
+				nick,
+				nintendont_version,
+				client_token,
+				pos,
+			} => {
+				let mut m = Map::new();
+				m.insert("nick".to_string(), nick.clone().into());
+				m.insert(
+					"nintendontVersion".to_string(),
+					nintendont_version.clone().into(),
+				);
+				ubjson::write_map(w, &m)?;
+				ubjson::write_key(w, "clientToken")?;
+				ubjson::write_bytes(w, &client_token.to_be_bytes())?;
+				ubjson::write_key(w, "pos")?;
+				ubjson::write_bytes(w, &pos.to_be_bytes())?;
+			}
+			Self::Replay {
+				pos,
+				next_pos,
+				force_pos,
+				data,
+			} => {
+				ubjson::write_key(w, "pos")?;
+				ubjson::write_bytes(w, &pos.to_be_bytes())?;
+				ubjson::write_key(w, "nextPos")?;
+				ubjson::write_bytes(w, &next_pos.to_be_bytes())?;
+				ubjson::write_key(w, "forcePos")?;
+				write!(w, "{}", if *force_pos { "T" } else { "F" })?;
+				ubjson::write_key(w, "data")?;
+				ubjson::write_bytes(w, data)?;
+			}
+			Self::KeepAlive => {}
+		}
+		write!(w, "}}}}")
+	}
+
+	/// Writes the message, preceded by its length.
+	pub fn write<W: Write>(&self, mut w: W) -> Result<()> {
+		let body = self.encode();
+		w.write_u32::<BigEndian>(body.len().try_into().unwrap())?;
+		w.write_all(&body)?;
+		Ok(())
+	}
+}
+
+/// Options for connecting to a console.
+#[derive(Clone, Debug)]
+pub struct Opts {
+	/// Ask the console to send data as soon as it's available.
+	pub is_realtime: bool,
+	/// How long to wait for any message (including keep-alives) before giving up on the
+	/// connection.
+	pub timeout: Duration,
+	/// Options for parsing each game.
+	pub parse: de::Opts,
+}
+
+impl Default for Opts {
+	fn default() -> Self {
+		Self {
+			is_realtime: false,
+			timeout: Duration::from_secs(20),
+			parse: Default::default(),
+		}
+	}
+}
+
+/// Something that happened on the console.
+pub enum Update {
+	/// A new game's Game Start was parsed.
+	Start,
+	/// More events were parsed from the current game (see [`Connection::state`]).
+	Events(Vec<u8>),
+	/// The current game reached Game End.
+	End(Box<Game>),
+	/// Data was skipped (see `force_pos` in [`Message::Replay`]), so the current game can't be
+	/// completed.
+	Abandoned(Box<Game>),
+}
+
+/// A connection to a console, parsing the games it sends.
+pub struct Connection {
+	addr: SocketAddr,
+	stream: TcpStream,
+	opts: Opts,
+	cursor: u64,
+	client_token: u32,
+	nick: String,
+	nintendont_version: String,
+	/// event data received but not yet parsed
+	buf: Vec<u8>,
+	state: Option<ParseState>,
+	pending: VecDeque<Update>,
+	/// whether `buf` starts at an event boundary
+	synced: bool,
+}
+
+impl Connection {
+	/// Connects to a console and performs the handshake, as a new client.
+	pub fn connect(addr: impl ToSocketAddrs, opts: Option<&Opts>) -> Result<Self> {
+		let addr = addr
+			.to_socket_addrs()?
+			.next()
+			.ok_or_else(|| err!("no address to connect to"))?;
+		let opts = opts.cloned().unwrap_or_default();
+		let stream = Self::open(addr, &opts)?;
+		let mut conn = Self {
+			addr,
+			stream,
+			opts,
+			cursor: 0,
+			client_token: 0,
+			nick: String::new(),
+			nintendont_version: String::new(),
+			buf: vec![],
+			state: None,
+			pending: VecDeque::new(),
+			synced: true,
+		};
+		conn.handshake()?;
+		Ok(conn)
+	}
+
+	fn open(addr: SocketAddr, opts: &Opts) -> Result<TcpStream> {
+		let stream = TcpStream::connect_timeout(&addr, opts.timeout)?;
+		stream.set_read_timeout(Some(opts.timeout))?;
+		stream.set_nodelay(true)?;
+		Ok(stream)
+	}
+
+	fn handshake(&mut self) -> Result<()> {
+		Message::HandshakeRequest {
+			cursor: self.cursor,
+			client_token: self.client_token,
+			is_realtime: self.opts.is_realtime,
+		}
+		.write(&mut self.stream)?;
+
+		loop {
+			match Message::read(&mut self.stream)? {
+				Some(Message::HandshakeResponse {
+					nick,
+					nintendont_version,
+					client_token,
+					pos,
+				}) => {
+					info!(
+						"Connected to {} ({}, {})",
+						self.addr, nick, nintendont_version
+					);
+					if self.cursor != 0 && pos != self.cursor {
+						warn!("Console resumed at {}, expected {}", pos, self.cursor);
+						self.abandon();
+					}
+					self.nick = nick;
+					self.nintendont_version = nintendont_version;
+					self.client_token = client_token;
+					self.cursor = pos;
+					return Ok(());
+				}
+				Some(Message::KeepAlive) => continue,
+				Some(m) => return Err(err!("expected handshake, but got: {:?}", m)),
+				None => return Err(err!("connection closed during handshake")),
+			}
+		}
+	}
+
+	/// Reconnects after a dropped connection (or timeout), resuming from the current cursor.
+	pub fn reconnect(&mut self) -> Result<()> {
+		info!("Reconnecting to {} at {}", self.addr, self.cursor);
+		self.stream = Self::open(self.addr, &self.opts)?;
+		self.handshake()
+	}
+
+	pub fn addr(&self) -> SocketAddr {
+		self.addr
+	}
+
+	/// Console's nickname, from the handshake.
+	pub fn nick(&self) -> &str {
+		&self.nick
+	}
+
+	/// Console's Nintendont version, from the handshake.
+	pub fn nintendont_version(&self) -> &str {
+		&self.nintendont_version
+	}
+
+	/// Position of the next byte of event data we expect.
+	pub fn cursor(&self) -> u64 {
+		self.cursor
+	}
+
+	/// Token identifying this client to the console across reconnects.
+	pub fn client_token(&self) -> u32 {
+		self.client_token
+	}
+
+	/// Parse state of the current game (`None` between games).
+	pub fn state(&self) -> Option<&ParseState> {
+		self.state.as_ref()
+	}
+
+	/// Discards the current game, e.g. because data was skipped.
+	fn abandon(&mut self) {
+		self.buf.clear();
+		self.synced = false;
+		if let Some(state) = self.state.take() {
+			self.pending
+				.push_back(Update::Abandoned(Box::new(Game::from(state))));
+		}
+	}
+
+	/// Reads & handles one message, returning any resulting updates. Blocks until a message
+	/// arrives or [`Opts::timeout`] elapses. Returns `None` if the console closed the connection.
+	pub fn recv(&mut self) -> Result<Option<Vec<Update>>> {
+		match Message::read(&mut self.stream)? {
+			None => {
+				info!("Connection to {} closed", self.addr);
+				Ok(None)
+			}
+			Some(Message::KeepAlive) => {
+				debug!("Keep-alive from {}", self.addr);
+				Ok(Some(self.pending.drain(..).collect()))
+			}
+			Some(Message::Replay {
+				pos,
+				next_pos,
+				force_pos,
+				data,
+			}) => {
+				if pos != self.cursor {
+					match force_pos {
+						true => {
+							warn!("Console skipped from {} to {}", self.cursor, pos);
+							self.abandon();
+						}
+						false => {
+							return Err(err!(
+								"unexpected data position: {} (expected {})",
+								pos,
+								self.cursor
+							))
+						}
+					}
+				}
+				self.cursor = next_pos;
+				self.buf.extend_from_slice(&data);
+				self.parse()?;
+				Ok(Some(self.pending.drain(..).collect()))
+			}
+			Some(m) => Err(err!("unexpected console message: {:?}", m)),
+		}
+	}
+
+	/// Parses as much of `buf` as possible, possibly spanning several games.
+	fn parse(&mut self) -> Result<()> {
+		loop {
+			if !self.synced {
+				// After skipped data we may be mid-game, so look for the start of the next game.
+				match self.buf.iter().position(|b| *b == Event::Payloads as u8) {
+					Some(idx) => drop(self.buf.drain(..idx)),
+					None => {
+						self.buf.clear();
+						return Ok(());
+					}
+				}
+			}
+
+			let mut codes = vec![];
+			let consumed = match parse_available(
+				&self.buf,
+				&mut self.state,
+				false,
+				Some(&self.opts.parse),
+				|_, code| {
+					codes.push(code);
+					Ok(())
+				},
+			) {
+				Ok(consumed) => consumed,
+				// not actually the start of a game; keep looking
+				Err(_) if !self.synced && self.state.is_none() => {
+					self.buf.drain(..1);
+					continue;
+				}
+				Err(e) => return Err(e),
+			};
+			if self.state.is_some() {
+				self.synced = true;
+			}
+			self.buf.drain(..consumed);
+
+			if codes.first() == Some(&(Event::GameStart as u8)) {
+				codes.remove(0);
+				self.pending.push_back(Update::Start);
+			}
+			let ended = codes.last() == Some(&(Event::GameEnd as u8));
+			if !codes.is_empty() {
+				self.pending.push_back(Update::Events(codes));
+			}
+			if !ended {
+				return Ok(());
+			}
+			let game = Game::from(self.state.take().unwrap());
+			self.pending.push_back(Update::End(Box::new(game)));
+		}
+	}
+}
+
+impl Iterator for Connection {
+	type Item = Result<Update>;
+
+	/// Blocks until there's an update. Returns `None` once the console closes the connection.
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(update) = self.pending.pop_front() {
+				return Some(Ok(update));
+			}
+			match self.recv() {
+				Ok(Some(updates)) => self.pending.extend(updates),
+				Ok(None) => return None,
+				Err(e) => return Some(Err(e)),
+			}
+		}
+	}
+}
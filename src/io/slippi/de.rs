@@ -3,11 +3,12 @@ use std::{
 	fs::{self, File},
 	io::{self, Read, Seek, SeekFrom, Write},
 	num::NonZeroU16,
+	ops::RangeInclusive,
 	path::PathBuf,
 };
 
 use arrow2::{array::MutableArray, offset::Offsets};
-use byteorder::ReadBytesExt;
+use byteorder::{ReadBytesExt, WriteBytesExt};
 use log::{debug, info, trace, warn};
 
 type BE = byteorder::BigEndian;
@@ -23,6 +24,10 @@ use crate::{
 
 type PayloadSizes = [Option<NonZeroU16>; 256];
 
+/// Every checkpoint saved by [`ParseState::checkpoint`] starts with this. The last byte is the
+/// checkpoint format version.
+const CHECKPOINT_SIGNATURE: [u8; 6] = [0x70, 0x65, 0x70, 0x70, 0x69, 0x01];
+
 #[derive(Clone, Debug)]
 pub struct Debug {
 	/// Output the each event's payload to `{dir}/{event_code}/{event_num}`.
@@ -88,6 +93,7 @@ impl From<PartialGame> for Game {
 	}
 }
 
+/// Incremental parser state, for parsing replays event-by-event (e.g. live games).
 pub struct ParseState {
 	payload_sizes: PayloadSizes,
 	bytes_read: usize,
@@ -95,6 +101,16 @@ pub struct ParseState {
 	split_accumulator: SplitAccumulator,
 	port_indexes: [usize; 4],
 	game: PartialGame,
+	/// offset of the event that opened the last frame, while that frame is still open
+	open_frame: Option<usize>,
+	/// frame IDs rolled back by the last event
+	rollback: Option<RangeInclusive<i32>>,
+}
+
+impl From<ParseState> for Game {
+	fn from(state: ParseState) -> Game {
+		Game::from(state.game)
+	}
 }
 
 impl game::Game for ParseState {
@@ -134,15 +150,139 @@ impl ParseState {
 		self.bytes_read
 	}
 
+	/// Number of frames whose events have all been parsed. Only the last frame can be
+	/// incomplete.
+	///
+	/// Before v3.0 there are no Frame End events, so a frame is only known to be complete once
+	/// the next one starts (or the game ends).
+	pub fn closed_frames(&self) -> usize {
+		self.game.frames.len() - usize::from(self.open_frame.is_some())
+	}
+
+	/// Copies all closed frames into immutable arrays, while parsing continues. See
+	/// [`mutable::Frame::snapshot`](frame::mutable::Frame::snapshot).
+	pub fn snapshot(&self) -> frame::immutable::Frame {
+		self.game.frames.snapshot(0, self.closed_frames())
+	}
+
+	/// Saves the parser's progress to `w`, so parsing can later be resumed with
+	/// [`restore`](Self::restore).
+	///
+	/// Frames are saved as re-encoded events. A frame that's still open is dropped, and will be
+	/// parsed again after restoring. Metadata isn't saved, since it follows the `raw` element.
+	///
+	/// Returns an error if the game's version is higher than
+	/// [`MAX_SUPPORTED_VERSION`](slippi::MAX_SUPPORTED_VERSION).
+	pub fn checkpoint<W: Write>(&self, mut w: W) -> Result<()> {
+		let version = self.game.start.slippi.version;
+		slippi::assert_max_version(version)?;
+
+		let mut events = vec![];
+		slippi::ser::write_preamble(
+			&mut events,
+			&self.game.start,
+			self.game.end.as_ref(),
+			self.game.gecko_codes.as_ref(),
+		)?;
+		for idx in 0..self.closed_frames() {
+			self.game.frames.write_one(&mut events, version, idx)?;
+		}
+		let resume_offset = match self.open_frame {
+			Some(offset) => offset,
+			None => {
+				if let Some(end) = &self.game.end {
+					slippi::ser::game_end(&mut events, end, version)?;
+				}
+				self.bytes_read
+			}
+		};
+
+		w.write_all(&CHECKPOINT_SIGNATURE)?;
+		w.write_u64::<BE>(resume_offset.try_into().unwrap())?;
+
+		let payload_sizes: Vec<_> = self
+			.payload_sizes
+			.iter()
+			.enumerate()
+			.filter_map(|(code, size)| size.map(|s| (code as u8, s.get())))
+			.collect();
+		w.write_u16::<BE>(payload_sizes.len().try_into().unwrap())?;
+		for (code, size) in payload_sizes {
+			w.write_u8(code)?;
+			w.write_u16::<BE>(size)?;
+		}
+
+		w.write_u32::<BE>(self.split_accumulator.actual_size)?;
+		w.write_u32::<BE>(self.split_accumulator.raw.len().try_into().unwrap())?;
+		w.write_all(&self.split_accumulator.raw)?;
+
+		w.write_u64::<BE>(events.len().try_into().unwrap())?;
+		w.write_all(&events)?;
+		Ok(())
+	}
+
+	/// Restores a parse state saved by [`checkpoint`](Self::checkpoint).
+	///
+	/// Parsing resumes [`bytes_read`](Self::bytes_read) bytes into the replay's `raw` element,
+	/// i.e. `15 + bytes_read` bytes into a `.slp` file (after the `raw` key & length).
+	pub fn restore<R: Read>(mut r: R, opts: Option<&Opts>) -> Result<Self> {
+		expect_bytes(&mut r, &CHECKPOINT_SIGNATURE)?;
+		let resume_offset: usize = r
+			.read_u64::<BE>()?
+			.try_into()
+			.map_err(|_| err!("invalid checkpoint offset"))?;
+
+		let mut payload_sizes = [None; 256];
+		for _ in 0..r.read_u16::<BE>()? {
+			let code = r.read_u8()?;
+			payload_sizes[code as usize] = NonZeroU16::new(r.read_u16::<BE>()?);
+		}
+
+		let actual_size = r.read_u32::<BE>()?;
+		let mut raw = vec![0; r.read_u32::<BE>()? as usize];
+		r.read_exact(&mut raw)?;
+
+		let mut events = vec![];
+		let len = r.read_u64::<BE>()?;
+		r.take(len).read_to_end(&mut events)?;
+
+		// Rebuild everything else by re-parsing the saved events.
+		let mut events = &events[..];
+		let mut state = parse_start(&mut events, opts)?;
+		while !events.is_empty() {
+			parse_event(&mut events, &mut state, opts)?;
+		}
+		// all saved frames are complete, even without Frame End events
+		state.frame_close();
+
+		state.payload_sizes = payload_sizes;
+		state.split_accumulator = SplitAccumulator { raw, actual_size };
+		state.bytes_read = resume_offset;
+		state.rollback = None;
+		Ok(state)
+	}
+
 	fn last_id(&self) -> Option<i32> {
 		self.game.frames.id.values().last().map(|id| *id)
 	}
 
+	/// Frame IDs rolled back by the last event, if it started re-simulating earlier frames. Any
+	/// state derived from those frames should be discarded, since they're about to be replaced.
+	pub fn rollback(&self) -> Option<RangeInclusive<i32>> {
+		self.rollback.clone()
+	}
+
 	fn frame_open(&mut self, id: i32) {
+		self.rollback = self
+			.last_id()
+			.filter(|last| id <= *last)
+			.map(|last| id..=last);
 		self.game.frames.id.push(Some(id));
+		self.open_frame = Some(self.bytes_read);
 	}
 
 	fn frame_close(&mut self) {
+		self.open_frame = None;
 		let len = self.game.frames.len();
 		for p in &mut self.game.frames.ports {
 			while p.leader.len() < len {
@@ -651,6 +791,8 @@ pub fn parse_start<R: Read>(mut r: R, opts: Option<&Opts>) -> Result<ParseState>
 		game,
 		port_indexes,
 		split_accumulator: Default::default(),
+		open_frame: None,
+		rollback: None,
 	})
 }
 
@@ -675,6 +817,8 @@ pub fn parse_event<R: Read>(mut r: R, state: &mut ParseState, opts: Option<&Opts
 		.get() as usize;
 	let mut buf = vec![0; size];
 	r.read_exact(&mut buf)?;
+	// only now that the whole event is here, so a partial event leaves the state untouched
+	state.rollback = None;
 
 	if code == Event::MessageSplitter as u8 {
 		if let Some(wrapped_event) = handle_splitter_event(&buf, &mut state.split_accumulator)? {
@@ -703,7 +847,13 @@ pub fn parse_event<R: Read>(mut r: R, state: &mut ParseState, opts: Option<&Opts
 				})
 			}
 			GameStart => return Err(err!("Duplicate start event")),
-			GameEnd => state.game.end = Some(game_end(&mut &*buf)?),
+			GameEnd => {
+				state.game.end = Some(game_end(&mut &*buf)?);
+				// no FrameEnd events before v3.0, so the last frame is still open
+				if state.game.start.slippi.version.lt(3, 0) {
+					state.frame_close();
+				}
+			}
 			FrameStart => {
 				// no FrameEnd events before v3.0, so simulate it
 				if state.game.start.slippi.version.lt(3, 0) {
@@ -972,12 +1122,6 @@ pub fn read<R: Read + Seek>(r: R, opts: Option<&Opts>) -> Result<Game> {
 		}
 	}
 
-	// FrameEnd doesn't exist until v3.0, so we simulate it in FrameStart/FramePre.
-	// But that means there can be a "dangling" frame that we need to close here.
-	if state.game.start.slippi.version.lt(3, 0) {
-		state.frame_close();
-	}
-
 	info!("Frames: {}", state.game.frames.len());
 
 	// Some replays have duplicated Game End events, which are safe to ignore.
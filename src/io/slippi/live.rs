@@ -0,0 +1,317 @@
+//! Following replays as they're written (e.g. by Dolphin during a game).
+//!
+//! [`Tail`] parses a single growing `.slp` file, picking up where it left off whenever more bytes
+//! are available. [`Follower`] watches a directory (such as Slippi's replay directory) for new
+//! replays, and tails each one in turn.
+//!
+//! Both stop at Game End. Metadata is written after that, when the replay is closed, so it isn't
+//! parsed here; use [`read`](crate::io::slippi::read) on the finished file if you need it.
+
+use std::{
+	collections::{HashSet, VecDeque},
+	fs::{self, File},
+	io::{self, Read},
+	path::{Path, PathBuf},
+	thread,
+	time::{Duration, SystemTime},
+};
+
+use log::{debug, info};
+
+use crate::{
+	game::immutable::Game,
+	io::{
+		slippi::de::{self, parse_event, parse_header, parse_start, Event, ParseState},
+		Error, Result,
+	},
+};
+
+/// Options for following replays.
+#[derive(Clone, Debug)]
+pub struct Opts {
+	/// How long to wait for more data before checking again.
+	pub poll_interval: Duration,
+	/// Whether replays already in the directory when following starts should be parsed too.
+	pub include_existing: bool,
+	/// Options for parsing each replay.
+	pub parse: de::Opts,
+}
+
+impl Default for Opts {
+	fn default() -> Self {
+		Self {
+			poll_interval: Duration::from_millis(100),
+			include_existing: false,
+			parse: Default::default(),
+		}
+	}
+}
+
+fn is_eof(e: &Error) -> bool {
+	matches!(e, Error::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof)
+}
+
+/// Parses as many complete events from `buf` as possible, stopping after Game End. The first
+/// events parsed are the header (if `header` is set) & Game Start, if `state` hasn't been
+/// initialized yet.
+///
+/// Calls `f` after each event, and returns the number of bytes consumed. Partial events are
+/// left unconsumed, and don't affect `state`.
+pub(crate) fn parse_available(
+	buf: &[u8],
+	state: &mut Option<ParseState>,
+	header: bool,
+	opts: Option<&de::Opts>,
+	mut f: impl FnMut(&ParseState, u8) -> Result<()>,
+) -> Result<usize> {
+	let mut consumed = 0;
+	loop {
+		let mut r = &buf[consumed..];
+		let result = match state {
+			None => match header {
+				true => parse_header(&mut r, opts).map(|_| ()),
+				false => Ok(()),
+			}
+			.and_then(|_| parse_start(&mut r, opts))
+			.map(|s| {
+				*state = Some(s);
+				Event::GameStart as u8
+			}),
+			Some(state) => parse_event(&mut r, state, opts),
+		};
+		match result {
+			Ok(code) => {
+				consumed = buf.len() - r.len();
+				f(state.as_ref().unwrap(), code)?;
+				if code == Event::GameEnd as u8 {
+					return Ok(consumed);
+				}
+			}
+			Err(e) if is_eof(&e) => return Ok(consumed),
+			Err(e) => return Err(e),
+		}
+	}
+}
+
+/// Follows a single replay file as it grows.
+pub struct Tail {
+	path: PathBuf,
+	file: File,
+	/// bytes read from the file but not yet parsed
+	buf: Vec<u8>,
+	state: Option<ParseState>,
+	ended: bool,
+}
+
+impl Tail {
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+		Ok(Self {
+			file: File::open(&path)?,
+			path,
+			buf: vec![],
+			state: None,
+			ended: false,
+		})
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Parse state so far (`None` until the Game Start event has been written).
+	pub fn state(&self) -> Option<&ParseState> {
+		self.state.as_ref()
+	}
+
+	pub fn into_state(self) -> Option<ParseState> {
+		self.state
+	}
+
+	/// Whether the Game End event has been parsed.
+	pub fn is_ended(&self) -> bool {
+		self.ended
+	}
+
+	/// Parses all complete events written since the last call, and returns their codes.
+	///
+	/// Partially-written events are left for the next call. Returns an empty list if nothing
+	/// new is available (including after Game End).
+	pub fn poll(&mut self, opts: Option<&de::Opts>) -> Result<Vec<u8>> {
+		if self.ended {
+			return Ok(vec![]);
+		}
+		self.file.read_to_end(&mut self.buf)?;
+
+		let mut codes = vec![];
+		let consumed = parse_available(&self.buf, &mut self.state, true, opts, |_, code| {
+			codes.push(code);
+			Ok(())
+		})?;
+		if codes.last() == Some(&(Event::GameEnd as u8)) {
+			self.ended = true;
+			debug!("{}: Game End", self.path.display());
+		}
+		match self.ended {
+			true => self.buf.clear(),
+			false => drop(self.buf.drain(..consumed)),
+		}
+		Ok(codes)
+	}
+}
+
+/// Something that happened in the followed directory.
+pub enum Update {
+	/// A new replay's Game Start was parsed.
+	Start { path: PathBuf },
+	/// More events were parsed from the current replay (see [`Follower::state`]).
+	Events { path: PathBuf, codes: Vec<u8> },
+	/// The current replay reached Game End.
+	End { path: PathBuf, game: Box<Game> },
+	/// The current replay was abandoned without a Game End, because a newer one appeared.
+	Abandoned {
+		path: PathBuf,
+		game: Option<Box<Game>>,
+	},
+}
+
+/// Watches a directory for new replays, tailing each one until Game End.
+pub struct Follower {
+	dir: PathBuf,
+	opts: Opts,
+	seen: HashSet<PathBuf>,
+	current: Option<Tail>,
+	pending: VecDeque<Update>,
+}
+
+fn is_replay(path: &Path) -> bool {
+	path.extension().is_some_and(|e| e == "slp")
+}
+
+impl Follower {
+	pub fn new(dir: impl AsRef<Path>, opts: Option<&Opts>) -> Result<Self> {
+		let opts = opts.cloned().unwrap_or_default();
+		let dir = dir.as_ref().to_path_buf();
+		let mut follower = Self {
+			dir,
+			seen: HashSet::new(),
+			current: None,
+			pending: VecDeque::new(),
+			opts,
+		};
+		if !follower.opts.include_existing {
+			follower.seen = follower.replays()?.into_iter().collect();
+		}
+		Ok(follower)
+	}
+
+	/// Parse state of the replay currently being followed.
+	pub fn state(&self) -> Option<&ParseState> {
+		self.current.as_ref().and_then(|t| t.state())
+	}
+
+	/// Path of the replay currently being followed.
+	pub fn path(&self) -> Option<&Path> {
+		self.current.as_ref().map(|t| t.path())
+	}
+
+	/// Replays in the directory, oldest first.
+	fn replays(&self) -> Result<Vec<PathBuf>> {
+		let mut replays = vec![];
+		for entry in fs::read_dir(&self.dir)? {
+			let entry = entry?;
+			let path = entry.path();
+			if is_replay(&path) && entry.file_type()?.is_file() {
+				let modified = entry
+					.metadata()?
+					.modified()
+					.unwrap_or(SystemTime::UNIX_EPOCH);
+				replays.push((modified, path));
+			}
+		}
+		replays.sort();
+		Ok(replays.into_iter().map(|(_, p)| p).collect())
+	}
+
+	/// Checks for new data without blocking, returning any updates.
+	pub fn poll(&mut self) -> Result<Vec<Update>> {
+		let mut updates = vec![];
+
+		let new: Vec<_> = self
+			.replays()?
+			.into_iter()
+			.filter(|p| !self.seen.contains(p))
+			.collect();
+
+		for path in new {
+			// finish whatever's left of the current replay before moving on
+			if let Some(mut tail) = self.current.take() {
+				self.poll_tail(&mut tail, &mut updates)?;
+				if !tail.is_ended() {
+					info!("Abandoning {}", tail.path().display());
+					updates.push(Update::Abandoned {
+						path: tail.path().to_path_buf(),
+						game: tail.into_state().map(|s| Box::new(Game::from(s))),
+					});
+				}
+			}
+			info!("Following {}", path.display());
+			self.seen.insert(path.clone());
+			self.current = Some(Tail::open(&path)?);
+		}
+
+		if let Some(mut tail) = self.current.take() {
+			self.poll_tail(&mut tail, &mut updates)?;
+			if !tail.is_ended() {
+				self.current = Some(tail);
+			}
+		}
+
+		Ok(updates)
+	}
+
+	/// Polls `tail`, adding an `End` update (and consuming its state) if it reached Game End.
+	fn poll_tail(&self, tail: &mut Tail, updates: &mut Vec<Update>) -> Result<()> {
+		if tail.is_ended() {
+			return Ok(());
+		}
+		let mut codes = tail.poll(Some(&self.opts.parse))?;
+		if codes.first() == Some(&(Event::GameStart as u8)) {
+			codes.remove(0);
+			updates.push(Update::Start {
+				path: tail.path().to_path_buf(),
+			});
+		}
+		if !codes.is_empty() {
+			updates.push(Update::Events {
+				path: tail.path().to_path_buf(),
+				codes,
+			});
+		}
+		if tail.is_ended() {
+			updates.push(Update::End {
+				path: tail.path().to_path_buf(),
+				game: Box::new(Game::from(tail.state.take().unwrap())),
+			});
+		}
+		Ok(())
+	}
+}
+
+impl Iterator for Follower {
+	type Item = Result<Update>;
+
+	/// Blocks until there's an update. Never returns `None`.
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(update) = self.pending.pop_front() {
+				return Some(Ok(update));
+			}
+			match self.poll() {
+				Ok(updates) if updates.is_empty() => thread::sleep(self.opts.poll_interval),
+				Ok(updates) => self.pending.extend(updates),
+				Err(e) => return Some(Err(e)),
+			}
+		}
+	}
+}
@@ -1,6 +1,10 @@
 //! Slippi (`.slp`) serialization.
 
+pub mod console;
 pub mod de;
+pub mod live;
+pub mod observer;
+pub mod relay;
 pub mod ser;
 
 use serde::{Deserialize, Serialize};
@@ -9,7 +13,7 @@ use std::{fmt, str};
 use crate::io::{parse_u8, Error, Result};
 
 pub use de::read;
-pub use ser::write;
+pub use ser::{write, SlpWriter};
 
 /// Peppi can read replays with higher versions than this, but that discards information.
 /// So we refuse to re-serialze such replays, to avoid inadvertent information loss.
@@ -0,0 +1,223 @@
+//! Callback-based live parsing.
+//!
+//! Implement [`Observer`] for the events you care about, and feed replay bytes to a [`Driver`] as
+//! they arrive. The driver tracks rollbacks, so [`Observer::on_frame_finalized`] sees each frame
+//! exactly once, in order, with its final data.
+
+use crate::{
+	frame::{transpose, FIRST_INDEX},
+	game::{Game as _, Port},
+	io::{
+		slippi::{
+			de::{self, Event, ParseState},
+			live::parse_available,
+		},
+		Result,
+	},
+};
+
+/// Handlers for live parsing events. All methods do nothing by default.
+///
+/// `state` is the parse state after the triggering event.
+#[allow(unused_variables)]
+pub trait Observer {
+	/// The Game Start event was parsed.
+	fn on_game_start(&mut self, state: &ParseState) {}
+
+	/// A frame can no longer be rolled back. Called once per frame ID, in order.
+	///
+	/// Before v3.7, replays don't say which frames are final, so every frame is considered final
+	/// as soon as it's complete.
+	fn on_frame_finalized(&mut self, state: &ParseState, frame: &transpose::Frame) {}
+
+	/// A frame with ID `id` (or earlier) is being re-simulated.
+	fn on_rollback(&mut self, state: &ParseState, id: i32) {}
+
+	/// An Item event was parsed for frame `id`. May be called again for the same item & frame
+	/// after a rollback.
+	fn on_item(&mut self, state: &ParseState, id: i32, item: &transpose::Item) {}
+
+	/// The player at `port` lost a stock on finalized frame `id`, leaving them with `stocks`.
+	fn on_stock_lost(&mut self, state: &ParseState, id: i32, port: Port, stocks: u8) {}
+
+	/// The Game End event was parsed. All frames have been finalized.
+	fn on_game_end(&mut self, state: &ParseState) {}
+}
+
+/// Feeds replay bytes to the parser, calling an [`Observer`] as events are parsed.
+pub struct Driver<O: Observer> {
+	observer: O,
+	opts: Option<de::Opts>,
+	state: Option<ParseState>,
+	/// bytes fed but not yet parsed
+	buf: Vec<u8>,
+	tracker: Tracker,
+	ended: bool,
+}
+
+impl<O: Observer> Driver<O> {
+	pub fn new(observer: O, opts: Option<&de::Opts>) -> Self {
+		Self {
+			observer,
+			opts: opts.cloned(),
+			state: None,
+			buf: vec![],
+			tracker: Tracker {
+				rows: vec![],
+				row_count: 0,
+				next_final: FIRST_INDEX,
+				stocks: [None; 4],
+			},
+			ended: false,
+		}
+	}
+
+	pub fn observer(&self) -> &O {
+		&self.observer
+	}
+
+	pub fn observer_mut(&mut self) -> &mut O {
+		&mut self.observer
+	}
+
+	pub fn into_observer(self) -> O {
+		self.observer
+	}
+
+	/// Parse state so far (`None` until the Game Start event has been fed).
+	pub fn state(&self) -> Option<&ParseState> {
+		self.state.as_ref()
+	}
+
+	pub fn into_state(self) -> Option<ParseState> {
+		self.state
+	}
+
+	/// Whether the Game End event has been parsed.
+	pub fn is_ended(&self) -> bool {
+		self.ended
+	}
+
+	/// Feeds more replay bytes (continuing from the end of the previous call), calling the
+	/// observer for each complete event. Incomplete events are buffered until more bytes
+	/// arrive. Bytes after Game End are ignored.
+	pub fn feed(&mut self, bytes: &[u8]) -> Result<()> {
+		if self.ended {
+			return Ok(());
+		}
+		self.buf.extend_from_slice(bytes);
+
+		let (observer, tracker) = (&mut self.observer, &mut self.tracker);
+		let consumed = parse_available(
+			&self.buf,
+			&mut self.state,
+			true,
+			self.opts.as_ref(),
+			|state, code| {
+				tracker.event(observer, state, code);
+				Ok(())
+			},
+		)?;
+
+		self.ended = self.state.as_ref().is_some_and(|s| s.end().is_some());
+		match self.ended {
+			true => self.buf.clear(),
+			false => drop(self.buf.drain(..consumed)),
+		}
+		Ok(())
+	}
+}
+
+/// Tracks frame finalization, rollbacks & stocks as events are parsed.
+struct Tracker {
+	/// row index of the latest occurrence of each frame ID, indexed by `id - FIRST_INDEX`
+	rows: Vec<usize>,
+	/// number of frame rows seen so far
+	row_count: usize,
+	/// next frame ID to finalize
+	next_final: i32,
+	/// stock counts as of the last finalized frame, by port
+	stocks: [Option<u8>; 4],
+}
+
+impl Tracker {
+	fn event<O: Observer>(&mut self, observer: &mut O, state: &ParseState, code: u8) {
+		let version = state.start().slippi.version;
+		let frames = state.frames();
+		let ids = frames.id.values();
+
+		// a new frame was opened (by Frame Start, or Frame Pre before v2.2)
+		if self.row_count < ids.len() {
+			self.row_count = ids.len();
+			let row = ids.len() - 1;
+			let id = ids[row];
+			let idx = (id - FIRST_INDEX) as usize;
+			if idx < self.rows.len() {
+				observer.on_rollback(state, id);
+			} else {
+				self.rows.resize(idx + 1, usize::MAX);
+			}
+			self.rows[idx] = row;
+
+			// no Frame End before v3.0, so the previous frame is complete once the next opens
+			if version.lt(3, 0) && row > 0 {
+				self.finalize(observer, state, ids[row - 1]);
+			}
+		}
+
+		let latest = ids.last().copied();
+		match Event::try_from(code) {
+			Ok(Event::GameStart) => {
+				for p in &state.start().players {
+					self.stocks[p.port as usize] = Some(p.stocks);
+				}
+				observer.on_game_start(state);
+			}
+			Ok(Event::GameEnd) => {
+				if let Some(id) = latest {
+					self.finalize(observer, state, id);
+				}
+				observer.on_game_end(state);
+			}
+			Ok(Event::Item) => {
+				let item = frames.item.as_ref().unwrap();
+				let item = item.transpose_one(item.len() - 1, version);
+				observer.on_item(state, latest.unwrap(), &item);
+			}
+			Ok(Event::FrameEnd) => {
+				let id = latest.unwrap();
+				let finalized = frames
+					.end
+					.as_ref()
+					.and_then(|e| e.latest_finalized_frame.as_ref())
+					.and_then(|f| f.values().last().copied())
+					.map_or(id, |f| f.min(id));
+				self.finalize(observer, state, finalized);
+			}
+			_ => {}
+		}
+	}
+
+	/// Finalizes all frames up to & including `id`.
+	fn finalize<O: Observer>(&mut self, observer: &mut O, state: &ParseState, id: i32) {
+		let version = state.start().slippi.version;
+		while self.next_final <= id {
+			let next = self.next_final;
+			self.next_final += 1;
+			let row = match self.rows.get((next - FIRST_INDEX) as usize) {
+				Some(row) if *row != usize::MAX => *row,
+				_ => continue,
+			};
+			let frame = state.frames().transpose_one(row, version);
+			for p in &frame.ports {
+				let stocks = p.leader.post.stocks;
+				let prev = &mut self.stocks[p.port as usize];
+				if prev.is_some_and(|prev| stocks < prev) {
+					observer.on_stock_lost(state, next, p.port, stocks);
+				}
+				*prev = Some(stocks);
+			}
+			observer.on_frame_finalized(state, &frame);
+		}
+	}
+}
@@ -0,0 +1,262 @@
+//! Re-broadcasting a live game to many TCP clients.
+//!
+//! A [`Relay`] is updated from a live source, such as a [`Tail`](super::live::Tail),
+//! [`Follower`](super::live::Follower) or console [`Connection`](super::console::Connection). It
+//! sends each client a stream of raw events in the same format as the console protocol's replay
+//! data: the contents of a replay's `raw` element, for one game after another.
+//!
+//! Clients that connect mid-game are sent Event Payloads, Game Start, and all frames so far before
+//! being caught up with everyone else. Events are re-encoded from the parsed game, so clients never
+//! see partial frames.
+//!
+//! Each client is written to by its own thread, so a slow client can't hold up the others. Clients
+//! that fall more than [`Opts::queue_len`] updates behind are disconnected.
+
+use std::{
+	io::{self, Write},
+	net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+	sync::{
+		mpsc::{self, SyncSender, TrySendError},
+		Arc,
+	},
+	thread,
+	time::Duration,
+};
+
+use log::{debug, info, warn};
+
+use crate::{
+	game::{self, immutable::Game, Game as _, GeckoCodes},
+	io::{
+		slippi::{self, de::ParseState, ser},
+		Result,
+	},
+};
+
+/// Options for relaying.
+#[derive(Clone, Debug)]
+pub struct Opts {
+	/// Clients that don't accept data within this time are disconnected.
+	pub write_timeout: Duration,
+	/// Maximum number of updates waiting to be sent to a client. Clients that fall further
+	/// behind are disconnected.
+	pub queue_len: usize,
+}
+
+impl Default for Opts {
+	fn default() -> Self {
+		Self {
+			write_timeout: Duration::from_secs(5),
+			queue_len: 1024,
+		}
+	}
+}
+
+/// A connected client, written to by a dedicated thread.
+struct Client {
+	addr: SocketAddr,
+	queue: SyncSender<Arc<[u8]>>,
+	stream: TcpStream,
+}
+
+impl Client {
+	fn new(addr: SocketAddr, stream: TcpStream, opts: &Opts) -> Result<Self> {
+		let (queue, updates) = mpsc::sync_channel::<Arc<[u8]>>(opts.queue_len);
+		let mut writer = stream.try_clone()?;
+		thread::spawn(move || {
+			for buf in updates {
+				if let Err(e) = writer.write_all(&buf) {
+					info!("Dropping {}: {}", addr, e);
+					return;
+				}
+			}
+		});
+		Ok(Self {
+			addr,
+			queue,
+			stream,
+		})
+	}
+
+	/// Queues `buf` for sending. If that fails, disconnects the client and returns `false`.
+	fn send(&self, buf: &Arc<[u8]>) -> bool {
+		match self.queue.try_send(buf.clone()) {
+			Ok(_) => return true,
+			Err(TrySendError::Full(_)) => info!("Dropping {}: too far behind", self.addr),
+			// the writer already logged why
+			Err(TrySendError::Disconnected(_)) => (),
+		}
+		// also unblocks the writer, if it's stuck writing
+		let _ = self.stream.shutdown(Shutdown::Both);
+		false
+	}
+}
+
+/// The game currently being relayed.
+struct Broadcast {
+	start: game::Start,
+	/// everything sent so far, for late joiners
+	events: Vec<u8>,
+	/// number of frames sent so far
+	frames: usize,
+}
+
+/// Broadcasts a live game to TCP clients.
+pub struct Relay {
+	listener: TcpListener,
+	opts: Opts,
+	clients: Vec<Client>,
+	current: Option<Broadcast>,
+	/// Game Start of the last game to end, so we don't broadcast it again
+	ended: Option<game::Start>,
+}
+
+impl Relay {
+	/// Starts listening for clients on `addr`.
+	pub fn bind(addr: impl ToSocketAddrs, opts: Option<&Opts>) -> Result<Self> {
+		let listener = TcpListener::bind(addr)?;
+		listener.set_nonblocking(true)?;
+		Ok(Self {
+			listener,
+			opts: opts.cloned().unwrap_or_default(),
+			clients: vec![],
+			current: None,
+			ended: None,
+		})
+	}
+
+	pub fn local_addr(&self) -> Result<SocketAddr> {
+		Ok(self.listener.local_addr()?)
+	}
+
+	/// Number of connected clients.
+	pub fn clients(&self) -> usize {
+		self.clients.len()
+	}
+
+	/// Accepts any pending connections, sending new clients the current game so far. Called
+	/// automatically by [`update`](Self::update) & [`finish`](Self::finish).
+	pub fn accept(&mut self) -> Result<()> {
+		loop {
+			let (stream, addr) = match self.listener.accept() {
+				Ok(x) => x,
+				Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+				Err(e) => return Err(e.into()),
+			};
+			info!("Relaying to {}", addr);
+			stream.set_nonblocking(false)?;
+			stream.set_write_timeout(Some(self.opts.write_timeout))?;
+			stream.set_nodelay(true)?;
+			let client = Client::new(addr, stream, &self.opts)?;
+			if let Some(b) = &self.current {
+				if !client.send(&b.events.as_slice().into()) {
+					continue;
+				}
+			}
+			self.clients.push(client);
+		}
+	}
+
+	/// Broadcasts whatever's new in `state` since the last update: Event Payloads & Game Start
+	/// for a new game, closed frames, and Game End.
+	///
+	/// Nothing is sent for a game until its first frame, so Gecko Codes can be included. Returns
+	/// an error if the game's version is higher than
+	/// [`MAX_SUPPORTED_VERSION`](slippi::MAX_SUPPORTED_VERSION).
+	pub fn update(&mut self, state: &ParseState) -> Result<()> {
+		self.accept()?;
+		if state.frames().len() == 0 && state.end().is_none() {
+			return Ok(());
+		}
+		if !self.begin(state.start(), state.gecko_codes().as_ref())? {
+			return Ok(());
+		}
+
+		let version = state.start().slippi.version;
+		let mut buf = vec![];
+		let b = self.current.as_mut().unwrap();
+		for idx in b.frames..state.closed_frames() {
+			state.frames().write_one(&mut buf, version, idx)?;
+		}
+		b.frames = state.closed_frames();
+		self.broadcast(buf);
+
+		if let Some(end) = state.end() {
+			self.end(end)?;
+		}
+		Ok(())
+	}
+
+	/// Broadcasts the rest of a finished game (for sources that produce an [`immutable::Game`](Game)
+	/// at Game End, rather than a final parse state), including Game End if present.
+	pub fn finish(&mut self, game: &Game) -> Result<()> {
+		self.accept()?;
+		if !self.begin(&game.start, game.gecko_codes.as_ref())? {
+			return Ok(());
+		}
+
+		let version = game.start.slippi.version;
+		let mut buf = vec![];
+		let b = self.current.as_mut().unwrap();
+		for idx in b.frames..game.frames.len() {
+			game.frames.write_one(&mut buf, version, idx)?;
+		}
+		b.frames = game.frames.len();
+		self.broadcast(buf);
+
+		match &game.end {
+			Some(end) => self.end(end),
+			None => {
+				self.ended = self.current.take().map(|b| b.start);
+				Ok(())
+			}
+		}
+	}
+
+	/// Starts broadcasting a game, unless it's the current game or has already ended. Returns
+	/// whether `start` is the current game.
+	fn begin(&mut self, start: &game::Start, gecko_codes: Option<&GeckoCodes>) -> Result<bool> {
+		if self.current.as_ref().is_some_and(|b| &b.start == start) {
+			return Ok(true);
+		}
+		if self.ended.as_ref() == Some(start) {
+			return Ok(false);
+		}
+		slippi::assert_max_version(start.slippi.version)?;
+		if self.current.is_some() {
+			warn!("New game before Game End, abandoning the current one");
+		}
+
+		let mut buf = vec![];
+		ser::write_preamble(&mut buf, start, None, gecko_codes)?;
+		self.current = Some(Broadcast {
+			start: start.clone(),
+			events: vec![],
+			frames: 0,
+		});
+		self.broadcast(buf);
+		Ok(true)
+	}
+
+	fn end(&mut self, end: &game::End) -> Result<()> {
+		let b = self.current.take().unwrap();
+		let mut buf = vec![];
+		ser::game_end(&mut buf, end, b.start.slippi.version)?;
+		self.broadcast(buf);
+		debug!("Game End ({} frames)", b.frames);
+		self.ended = Some(b.start);
+		Ok(())
+	}
+
+	/// Queues `buf` for all clients (dropping any that fail), and records it for late joiners.
+	fn broadcast(&mut self, buf: Vec<u8>) {
+		if buf.is_empty() {
+			return;
+		}
+		let buf: Arc<[u8]> = buf.into();
+		self.clients.retain(|c| c.send(&buf));
+		if let Some(b) = &mut self.current {
+			b.events.extend_from_slice(&buf);
+		}
+	}
+}
@@ -1,4 +1,4 @@
-use std::io::Write;
+use std::io::{Seek, SeekFrom, Write};
 
 use byteorder::WriteBytesExt;
 
@@ -6,9 +6,15 @@ use crate::{
 	frame::immutable::{
 		DreamlandWhispy, End, FodPlatform, Frame, Item, Post, Pre, StadiumTransformation, Start,
 	},
-	game::{self, immutable::Game, GeckoCodes, Player, PlayerType, Port, MAX_PLAYERS, NUM_PORTS},
+	game::{
+		self, immutable::Game, shift_jis::MeleeString, GeckoCodes, Player, PlayerType, Port,
+		MAX_PLAYERS, NUM_PORTS,
+	},
 	io::{
-		slippi::{self, de::Event},
+		slippi::{
+			self,
+			de::{Event, ParseState},
+		},
 		ubjson, Result,
 	},
 };
@@ -54,21 +60,23 @@ impl PayloadSizes {
 	}
 }
 
-fn payload_sizes(game: &Game) -> PayloadSizes {
+fn payload_sizes(
+	start: &game::Start,
+	end: Option<&game::End>,
+	gecko_codes: Option<&GeckoCodes>,
+) -> PayloadSizes {
 	let mut sizes = PayloadSizes::new();
-	let ver = game.start.slippi.version.clone();
+	let ver = start.slippi.version;
 
 	const FRAME_NUMBER: usize = std::mem::size_of::<i32>();
 	const PORT: usize = 2 * std::mem::size_of::<u8>(); // port number + is_follower
 
-	sizes.push(Event::GameStart, game.start.bytes.0.len());
+	sizes.push(Event::GameStart, start.bytes.0.len());
 	sizes.push(Event::FramePre, FRAME_NUMBER + PORT + Pre::size(ver));
 	sizes.push(Event::FramePost, FRAME_NUMBER + PORT + Post::size(ver));
 	sizes.push(
 		Event::GameEnd,
-		game.end
-			.as_ref()
-			.map_or(game::End::size(ver), |e| e.bytes.0.len()),
+		end.map_or(game::End::size(ver), |e| e.bytes.0.len()),
 	);
 
 	if ver.gte(2, 2) {
@@ -78,7 +86,7 @@ fn payload_sizes(game: &Game) -> PayloadSizes {
 			if ver.gte(3, 0) {
 				sizes.push(Event::FrameEnd, FRAME_NUMBER + End::size(ver));
 				if ver.gte(3, 3) {
-					if let Some(codes) = &game.gecko_codes {
+					if let Some(codes) = gecko_codes {
 						// discard higher-order bits of actual_size, matching Slippi's behavior
 						sizes.push(Event::GeckoCodes, codes.actual_size as u16 as usize);
 						sizes.push(Event::MessageSplitter, 516);
@@ -132,7 +140,19 @@ fn player(start: &game::Start, port: usize) -> Option<&Player> {
 	}
 }
 
-fn _game_start(s: &game::Start) -> Result<Vec<u8>> {
+/// Parsed tags are re-encoded as-is (even characters Melee can't show), so they round-trip.
+/// Tags that plain Shift JIS would change, such as `¥` from [MeleeString::from_display], are
+/// encoded as Melee shows them instead.
+fn name_tag_bytes(tag: &MeleeString) -> Result<Vec<u8>> {
+	if let Ok(bytes) = tag.bytes() {
+		if MeleeString::try_from(&*bytes).is_ok_and(|t| t == *tag) {
+			return Ok(bytes.into_owned());
+		}
+	}
+	tag.name_tag_bytes()
+}
+
+pub(crate) fn _game_start(s: &game::Start) -> Result<Vec<u8>> {
 	let mut buf = s.bytes.0.clone();
 	let mut b = &mut buf[..];
 
@@ -213,8 +233,11 @@ fn _game_start(s: &game::Start) -> Result<Vec<u8>> {
 	if ver.gte(1, 3) {
 		for n in 0..NUM_PORTS {
 			if let Some(p) = player(s, n) {
-				let name_tag = p.name_tag.as_ref().unwrap().bytes();
-				b.write_all(&name_tag.to_owned())?; // 0x10n + 0x161
+				let name_tag = name_tag_bytes(p.name_tag.as_ref().unwrap())?;
+				if name_tag.len() > 16 {
+					return Err(err!("name tag must be no more than 16 bytes"));
+				}
+				b.write_all(&name_tag)?; // 0x10n + 0x161
 				b.write_all(&vec![0; 16 - name_tag.len()])?;
 			} else {
 				b = &mut b[16..];
@@ -246,7 +269,7 @@ fn _game_start(s: &game::Start) -> Result<Vec<u8>> {
 		for n in 0..NUM_PORTS {
 			// 0x1Fn + 0x1A5
 			if let Some(p) = player(s, n) {
-				let bytes = p.netplay.as_ref().unwrap().name.bytes();
+				let bytes = p.netplay.as_ref().unwrap().name.bytes()?;
 				if bytes.len() > 30 {
 					return Err(err!("netplay name must be no more than 30 bytes"));
 				}
@@ -259,7 +282,7 @@ fn _game_start(s: &game::Start) -> Result<Vec<u8>> {
 		for n in 0..NUM_PORTS {
 			// 0xAn + 0x221
 			if let Some(p) = player(s, n) {
-				let bytes = p.netplay.as_ref().unwrap().code.bytes();
+				let bytes = p.netplay.as_ref().unwrap().code.bytes()?;
 				if bytes.len() > 9 {
 					return Err(err!("netplay code must be no more than 9 bytes"));
 				}
@@ -320,7 +343,7 @@ fn game_start<W: Write>(w: &mut W, s: &game::Start, ver: slippi::Version) -> Res
 	Ok(w.write_all(&_game_start(s)?)?)
 }
 
-fn game_end<W: Write>(w: &mut W, e: &game::End, ver: slippi::Version) -> Result<()> {
+pub(crate) fn game_end<W: Write>(w: &mut W, e: &game::End, ver: slippi::Version) -> Result<()> {
 	w.write_u8(Event::GameEnd as u8)?;
 	w.write_u8(e.method as u8)?;
 	if ver.gte(2, 0) {
@@ -391,16 +414,16 @@ fn gecko_codes_size(gecko_codes: &GeckoCodes) -> u32 {
 	num_blocks * (512 + 5)
 }
 
-/// Writes a replay to `w` in Slippi (`.slp`) format.
-///
-/// Returns an error if the game's version is higher than `MAX_SUPPORTED_VERSION`.
-pub fn write<W: Write>(w: &mut W, game: &Game) -> Result<()> {
-	slippi::assert_max_version(game.start.slippi.version)?;
-
-	let payload_sizes = payload_sizes(game);
-
-	w.write_all(&slippi::FILE_SIGNATURE)?;
-	w.write_u32::<BE>(payload_sizes.raw_size(game))?;
+/// Writes the events that precede frame data: Event Payloads, Game Start, and Gecko Codes (if
+/// any). `end` determines the Game End payload size; if `None`, the size for the game's version is
+/// assumed.
+pub(crate) fn write_preamble<W: Write>(
+	w: &mut W,
+	start: &game::Start,
+	end: Option<&game::End>,
+	codes: Option<&GeckoCodes>,
+) -> Result<()> {
+	let payload_sizes = payload_sizes(start, end, codes);
 
 	w.write_u8(Event::Payloads as u8)?;
 	// see "off-by-one" note in `de::parse_payloads`
@@ -410,13 +433,29 @@ pub fn write<W: Write>(w: &mut W, game: &Game) -> Result<()> {
 		w.write_u16::<BE>(size)?;
 	}
 
-	let ver = game.start.slippi.version;
-	game_start(w, &game.start, ver)?;
+	game_start(w, start, start.slippi.version)?;
 
-	if let Some(codes) = &game.gecko_codes {
+	if let Some(codes) = codes {
 		gecko_codes(w, codes)?;
 	}
 
+	Ok(())
+}
+
+/// Writes a replay to `w` in Slippi (`.slp`) format.
+///
+/// Returns an error if the game's version is higher than `MAX_SUPPORTED_VERSION`.
+pub fn write<W: Write>(w: &mut W, game: &Game) -> Result<()> {
+	slippi::assert_max_version(game.start.slippi.version)?;
+
+	let payload_sizes = payload_sizes(&game.start, game.end.as_ref(), game.gecko_codes.as_ref());
+
+	w.write_all(&slippi::FILE_SIGNATURE)?;
+	w.write_u32::<BE>(payload_sizes.raw_size(game))?;
+
+	write_preamble(w, &game.start, game.end.as_ref(), game.gecko_codes.as_ref())?;
+
+	let ver = game.start.slippi.version;
 	game.frames.write(w, ver)?;
 
 	if let Some(end) = &game.end {
@@ -426,7 +465,15 @@ pub fn write<W: Write>(w: &mut W, game: &Game) -> Result<()> {
 		}
 	}
 
-	if let Some(metadata) = &game.metadata {
+	write_trailer(w, game.metadata.as_ref())
+}
+
+/// Writes the metadata (if any) and closes the top-level map.
+fn write_trailer<W: Write>(
+	w: &mut W,
+	metadata: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> Result<()> {
+	if let Some(metadata) = metadata {
 		w.write_all(&[
 			0x55, 0x08, 0x6d, 0x65, 0x74, 0x61, 0x64, 0x61, 0x74, 0x61, 0x7b,
 		])?;
@@ -438,3 +485,148 @@ pub fn write<W: Write>(w: &mut W, game: &Game) -> Result<()> {
 
 	Ok(())
 }
+
+/// Writes a replay incrementally, as its events arrive (e.g. while recording a live game).
+///
+/// Like Slippi itself, this first writes a header with a `raw` length of zero, and fills in the
+/// real length when the replay is [finished](Self::finish). Replays written this way are
+/// identical to ones written by [`write`].
+pub struct SlpWriter<W: Write + Seek> {
+	w: W,
+	/// Game Start of the game being written, once the preamble has been written
+	start: Option<game::Start>,
+	/// bytes written so far in the `raw` element
+	raw_len: u32,
+	/// number of frames written so far
+	frames: usize,
+	ended: bool,
+}
+
+impl<W: Write + Seek> SlpWriter<W> {
+	/// Writes the file header.
+	pub fn new(mut w: W) -> Result<Self> {
+		w.write_all(&slippi::FILE_SIGNATURE)?;
+		w.write_u32::<BE>(0)?;
+		Ok(Self {
+			w,
+			start: None,
+			raw_len: 0,
+			frames: 0,
+			ended: false,
+		})
+	}
+
+	/// Number of frames written so far.
+	pub fn frames(&self) -> usize {
+		self.frames
+	}
+
+	/// Whether Game End has been written.
+	pub fn is_ended(&self) -> bool {
+		self.ended
+	}
+
+	fn write_raw(&mut self, buf: &[u8]) -> Result<()> {
+		self.w.write_all(buf)?;
+		self.raw_len = u32::try_from(buf.len())
+			.ok()
+			.and_then(|len| self.raw_len.checked_add(len))
+			.ok_or_else(|| err!("replay too large"))?;
+		Ok(())
+	}
+
+	/// Writes the preamble, if it hasn't been written yet. Returns an error if `start` is for a
+	/// different game than the one being written.
+	fn begin(
+		&mut self,
+		start: &game::Start,
+		end: Option<&game::End>,
+		gecko_codes: Option<&GeckoCodes>,
+	) -> Result<()> {
+		match &self.start {
+			Some(s) if s == start => Ok(()),
+			Some(_) => Err(err!("can't write events from a different game")),
+			None => {
+				slippi::assert_max_version(start.slippi.version)?;
+				let mut buf = vec![];
+				write_preamble(&mut buf, start, end, gecko_codes)?;
+				self.write_raw(&buf)?;
+				self.start = Some(start.clone());
+				Ok(())
+			}
+		}
+	}
+
+	fn end(&mut self, end: &game::End, double: bool) -> Result<()> {
+		let mut buf = vec![];
+		let ver = self.start.as_ref().unwrap().slippi.version;
+		game_end(&mut buf, end, ver)?;
+		if double {
+			game_end(&mut buf, end, ver)?;
+		}
+		self.write_raw(&buf)?;
+		self.ended = true;
+		Ok(())
+	}
+
+	/// Writes whatever's new in `state` since the last update: the preamble (Event Payloads, Game
+	/// Start & Gecko Codes), closed frames, and Game End.
+	///
+	/// Nothing is written until the game's first frame, since Gecko Codes arrive after Game Start.
+	pub fn update(&mut self, state: &ParseState) -> Result<()> {
+		use game::Game as _;
+		if self.ended || (state.frames().len() == 0 && state.end().is_none()) {
+			return Ok(());
+		}
+		self.begin(state.start(), None, state.gecko_codes().as_ref())?;
+
+		let ver = state.start().slippi.version;
+		let mut buf = vec![];
+		for idx in self.frames..state.closed_frames() {
+			state.frames().write_one(&mut buf, ver, idx)?;
+		}
+		self.write_raw(&buf)?;
+		self.frames = state.closed_frames();
+
+		if let Some(end) = state.end() {
+			self.end(end, false)?;
+		}
+		Ok(())
+	}
+
+	/// Writes whatever hasn't already been written of a (possibly partial) parsed game.
+	pub fn write_game(&mut self, game: &Game) -> Result<()> {
+		if self.ended {
+			return Ok(());
+		}
+		self.begin(&game.start, game.end.as_ref(), game.gecko_codes.as_ref())?;
+
+		let ver = game.start.slippi.version;
+		let mut buf = vec![];
+		for idx in self.frames..game.frames.len() {
+			game.frames.write_one(&mut buf, ver, idx)?;
+		}
+		self.write_raw(&buf)?;
+		self.frames = game.frames.len();
+
+		if let Some(end) = &game.end {
+			self.end(end, game.quirks.is_some_and(|q| q.double_game_end))?;
+		}
+		Ok(())
+	}
+
+	/// Writes `metadata` (if any), fills in the `raw` length, and returns the underlying writer.
+	pub fn finish(
+		mut self,
+		metadata: Option<&serde_json::Map<String, serde_json::Value>>,
+	) -> Result<W> {
+		write_trailer(&mut self.w, metadata)?;
+		let pos = self.w.stream_position()?;
+		self.w
+			.seek(SeekFrom::Start(slippi::FILE_SIGNATURE.len() as u64))?;
+		self.w.write_u32::<BE>(self.raw_len)?;
+		self.w.seek(SeekFrom::Start(pos))?;
+		self.w.flush()?;
+		Ok(self.w)
+	}
+}
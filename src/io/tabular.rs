@@ -0,0 +1,120 @@
+//! Tabular export of frame data, as CSV or JSON Lines.
+//!
+//! Each row is a single frame, with one column per field of each character (e.g.
+//! `p1_leader_post_position_x`). See [`flat`] for how columns are named, and which are present for
+//! a given replay. Per-frame lists (items & stage data) aren't included.
+
+use std::io::Write;
+
+use arrow2::{
+	array::{Array, PrimitiveArray},
+	datatypes::DataType,
+};
+
+use crate::{
+	frame::{flat, Rollbacks},
+	game::{immutable::Game, port_occupancy},
+	io::Result,
+};
+
+/// Output format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+	/// Comma-separated values, with a header row. Nulls are written as empty cells.
+	#[default]
+	Csv,
+	/// One JSON object per line. Nulls (and non-finite floats) are written as `null`.
+	JsonLines,
+}
+
+/// Options for tabular export.
+#[derive(Clone, Debug, Default)]
+pub struct Opts {
+	pub format: Format,
+	/// Drop rolled-back frames, keeping only one of each frame ID. See
+	/// [`Frame::rollbacks`](crate::frame::immutable::Frame::rollbacks).
+	pub rollbacks: Option<Rollbacks>,
+}
+
+/// Writes a game's frame data to `w`, one row per frame.
+pub fn write<W: Write>(mut w: W, game: Game, opts: Option<&Opts>) -> Result<()> {
+	let format = opts.map(|o| o.format).unwrap_or_default();
+	let rollbacks = opts
+		.and_then(|o| o.rollbacks)
+		.map(|keep| game.frames.rollbacks(keep));
+
+	let version = game.start.slippi.version;
+	let ports = port_occupancy(&game.start);
+	let len = game.frames.len();
+	let columns: Vec<_> = flat::columns(game.frames, version, &ports)
+		.into_iter()
+		.filter(|(_, array)| !matches!(array.data_type(), DataType::List(_)))
+		.collect();
+
+	if format == Format::Csv {
+		let names: Vec<_> = columns.iter().map(|(name, _)| name.as_str()).collect();
+		writeln!(w, "{}", names.join(","))?;
+	}
+
+	for i in 0..len {
+		if rollbacks.as_ref().is_some_and(|r| r[i]) {
+			continue;
+		}
+		match format {
+			Format::Csv => {
+				for (n, (_, array)) in columns.iter().enumerate() {
+					if n > 0 {
+						w.write_all(b",")?;
+					}
+					if array.is_valid(i) {
+						write_value(&mut w, array.as_ref(), i, false)?;
+					}
+				}
+			}
+			Format::JsonLines => {
+				w.write_all(b"{")?;
+				for (n, (name, array)) in columns.iter().enumerate() {
+					if n > 0 {
+						w.write_all(b",")?;
+					}
+					write!(w, "\"{}\":", name)?;
+					if array.is_valid(i) {
+						write_value(&mut w, array.as_ref(), i, true)?;
+					} else {
+						w.write_all(b"null")?;
+					}
+				}
+				w.write_all(b"}")?;
+			}
+		}
+		w.write_all(b"\n")?;
+	}
+
+	Ok(())
+}
+
+fn value<T: arrow2::types::NativeType>(array: &dyn Array, i: usize) -> T {
+	array
+		.as_any()
+		.downcast_ref::<PrimitiveArray<T>>()
+		.unwrap()
+		.value(i)
+}
+
+fn write_value<W: Write>(w: &mut W, array: &dyn Array, i: usize, json: bool) -> Result<()> {
+	use DataType::*;
+	match array.data_type() {
+		Int8 => write!(w, "{}", value::<i8>(array, i))?,
+		UInt8 => write!(w, "{}", value::<u8>(array, i))?,
+		Int16 => write!(w, "{}", value::<i16>(array, i))?,
+		UInt16 => write!(w, "{}", value::<u16>(array, i))?,
+		Int32 => write!(w, "{}", value::<i32>(array, i))?,
+		UInt32 => write!(w, "{}", value::<u32>(array, i))?,
+		Float32 => match value::<f32>(array, i) {
+			x if json && !x.is_finite() => w.write_all(b"null")?,
+			x => write!(w, "{}", x)?,
+		},
+		t => return Err(err!("unsupported data type: {:?}", t)),
+	}
+	Ok(())
+}
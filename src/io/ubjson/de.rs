@@ -1,39 +1,131 @@
 use std::io::Read;
 
 use byteorder::{BigEndian, ReadBytesExt};
-use serde_json::{Map, Value};
+use serde_json::{Map, Number, Value};
 
 use crate::io::Result;
 
-fn to_utf8<R: Read>(r: &mut R) -> Result<String> {
-	let length = r.read_u8()?;
-	let mut buf = vec![0; length as usize];
-	r.read_exact(&mut buf)?;
-	Ok(String::from_utf8(buf)?)
+/// Reads an integer of the type indicated by `marker` (used for lengths & counts).
+fn to_len<R: Read>(r: &mut R, marker: u8) -> Result<usize> {
+	let len = match marker {
+		0x55 => r.read_u8()? as i64,               // "U": u8
+		0x69 => r.read_i8()? as i64,               // "i": i8
+		0x49 => r.read_i16::<BigEndian>()? as i64, // "I": i16
+		0x6c => r.read_i32::<BigEndian>()? as i64, // "l": i32
+		0x4c => r.read_i64::<BigEndian>()?,        // "L": i64
+		c => return Err(err!("unexpected UBJSON length type: {}", c)),
+	};
+	usize::try_from(len).map_err(|_| err!("negative UBJSON length: {}", len))
 }
 
-fn to_val<R: Read>(r: &mut R) -> Result<Value> {
-	match r.read_u8()? {
+/// Reads exactly `len` bytes. Lengths are untrusted, so the buffer grows with the data actually
+/// read rather than being allocated up front.
+fn read_bytes<R: Read>(r: &mut R, len: usize) -> Result<Vec<u8>> {
+	let mut buf = vec![];
+	r.by_ref().take(len as u64).read_to_end(&mut buf)?;
+	if buf.len() < len {
+		return Err(err!(
+			"UBJSON length {} exceeds remaining data ({} bytes)",
+			len,
+			buf.len()
+		));
+	}
+	Ok(buf)
+}
+
+fn to_utf8<R: Read>(r: &mut R, marker: u8) -> Result<String> {
+	let length = to_len(r, marker)?;
+	Ok(String::from_utf8(read_bytes(r, length)?)?)
+}
+
+fn to_float(f: f64) -> Result<Value> {
+	Number::from_f64(f)
+		.map(Value::Number)
+		.ok_or_else(|| err!("invalid UBJSON float: {}", f))
+}
+
+/// Reads a value whose type marker has already been consumed.
+fn to_typed_val<R: Read>(r: &mut R, marker: u8) -> Result<Value> {
+	match marker {
+		0x5a => Ok(Value::Null),        // "Z"
+		0x54 => Ok(Value::Bool(true)),  // "T"
+		0x46 => Ok(Value::Bool(false)), // "F"
+		0x55 => Ok(Value::from(r.read_u8()?)),
+		0x69 => Ok(Value::from(r.read_i8()?)),
+		0x49 => Ok(Value::from(r.read_i16::<BigEndian>()?)),
+		0x6c => Ok(Value::from(r.read_i32::<BigEndian>()?)),
+		0x4c => Ok(Value::from(r.read_i64::<BigEndian>()?)),
+		0x64 => to_float(r.read_f32::<BigEndian>()? as f64), // "d"
+		0x44 => to_float(r.read_f64::<BigEndian>()?),        // "D"
+		// "C": char
+		0x43 => Ok(Value::String(char::from(r.read_u8()?).to_string())),
 		// "S": str
-		0x53 => match r.read_u8()? {
-			0x55 => Ok(Value::String(to_utf8(r)?)),
-			c => Err(err!("Expected 0x55 for string length, but got: {}", c)),
-		},
-		// "l": i32
-		0x6c => Ok(Value::Number(serde_json::Number::from(
-			r.read_i32::<BigEndian>()?,
-		))),
+		0x53 => {
+			let marker = r.read_u8()?;
+			Ok(Value::String(to_utf8(r, marker)?))
+		}
+		// "[": array
+		0x5b => Ok(Value::Array(read_array(r)?)),
 		// "{": map
 		0x7b => Ok(Value::Object(read_map(r)?)),
 		c => Err(err!("unexpected UBJSON value type: {}", c)),
 	}
 }
 
+fn to_val<R: Read>(r: &mut R) -> Result<Value> {
+	let marker = r.read_u8()?;
+	to_typed_val(r, marker)
+}
+
+/// Reads an array's contents, assuming the opening `[` was already consumed. Handles the
+/// optimized (`$` type & `#` count) forms.
+fn read_array<R: Read>(r: &mut R) -> Result<Vec<Value>> {
+	let mut marker = r.read_u8()?;
+	let mut r#type = None;
+	if marker == 0x24 {
+		// "$": all elements have this type
+		let t = r.read_u8()?;
+		if matches!(t, 0x5a | 0x54 | 0x46) {
+			// elements with no payload would let a tiny count claim an arbitrarily large array
+			return Err(err!("unsupported UBJSON container type: {}", t));
+		}
+		r#type = Some(t);
+		marker = r.read_u8()?;
+		if marker != 0x23 {
+			return Err(err!(
+				"expected UBJSON count after type, but got: {}",
+				marker
+			));
+		}
+	}
+	if marker == 0x23 {
+		// "#": element count
+		let count_marker = r.read_u8()?;
+		let count = to_len(r, count_marker)?;
+		if r#type == Some(0x55) {
+			// fast path for byte arrays
+			return Ok(read_bytes(r, count)?.into_iter().map(Value::from).collect());
+		}
+		return (0..count)
+			.map(|_| match r#type {
+				Some(t) => to_typed_val(r, t),
+				None => to_val(r),
+			})
+			.collect();
+	}
+	let mut values = vec![];
+	while marker != 0x5d {
+		// "]"
+		values.push(to_typed_val(r, marker)?);
+		marker = r.read_u8()?;
+	}
+	Ok(values)
+}
+
 fn to_key<R: Read>(r: &mut R) -> Result<Option<String>> {
 	match r.read_u8()? {
-		0x55 => Ok(Some(to_utf8(r)?)),
 		0x7d => Ok(None),
-		c => Err(err!("unexpected UBJSON key type: {}", c)),
+		c => Ok(Some(to_utf8(r, c)?)),
 	}
 }
 
@@ -48,3 +140,8 @@ pub(crate) fn read_map<R: Read>(r: &mut R) -> Result<Map<String, Value>> {
 	} {}
 	Ok(m)
 }
+
+/// Reads a complete value, including its type marker.
+pub(crate) fn read_value<R: Read>(r: &mut R) -> Result<Value> {
+	to_val(r)
+}
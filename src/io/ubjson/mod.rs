@@ -1,5 +1,5 @@
 pub(crate) mod de;
 pub(crate) mod ser;
 
-pub(crate) use de::read_map;
-pub(crate) use ser::write_map;
+pub(crate) use de::{read_map, read_value};
+pub(crate) use ser::{write_bytes, write_key, write_map};
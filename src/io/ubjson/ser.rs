@@ -10,6 +10,18 @@ fn write_utf8<W: Write>(w: &mut W, s: &str) -> Result<()> {
 	Ok(())
 }
 
+/// Writes a map key.
+pub(crate) fn write_key<W: Write>(w: &mut W, k: &str) -> Result<()> {
+	write_utf8(w, k)
+}
+
+/// Writes a byte array, in optimized (`[$U#l`) form.
+pub(crate) fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> Result<()> {
+	write!(w, "[$U#l")?;
+	w.write_i32::<BigEndian>(bytes.len().try_into().unwrap())?;
+	w.write_all(bytes)
+}
+
 pub(crate) fn write_map<W: Write>(w: &mut W, map: &Map<String, Value>) -> Result<()> {
 	for (k, v) in map {
 		write_utf8(w, k)?;
@@ -0,0 +1,170 @@
+use std::{fs::File, io::Cursor, path::Path};
+
+use arrow2::{
+	array::{PrimitiveArray, StructArray, Utf8Array},
+	io::ipc::read::{read_file_metadata, FileReader},
+};
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	frame::immutable::Frame,
+	game::Game as _,
+	io::{
+		batch::{self, Opts},
+		peppi as slpp,
+		slippi::Version,
+		Error,
+	},
+};
+
+mod common;
+use common::{game, get_path, replays, temp_dir};
+
+fn opts() -> Opts {
+	Opts {
+		threads: 4,
+		max_pending: 4,
+		..Default::default()
+	}
+}
+
+#[test]
+fn for_each() {
+	let paths = replays();
+	let mut seen = vec![];
+	batch::for_each(&paths, Some(&opts()), |idx, path, game| {
+		assert_eq!(path, paths[idx]);
+		let expected = common::read_game(path, false);
+		assert_eq!(game.is_ok(), expected.is_ok(), "{:?}", path);
+		if let (Ok(game), Ok(expected)) = (game, expected) {
+			assert_eq!(game.start, expected.start);
+			assert_eq!(game.len(), expected.len());
+		}
+		seen.push(idx);
+		Ok(())
+	})
+	.unwrap();
+	assert_eq!(seen, (0..paths.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn stop() {
+	let paths = replays();
+	let mut calls = 0;
+	let result = batch::for_each(&paths, Some(&opts()), |idx, _, _| {
+		calls += 1;
+		match idx {
+			2 => Err(Error::InvalidData("stop".to_string())),
+			_ => Ok(()),
+		}
+	});
+	assert!(result.is_err());
+	assert_eq!(calls, 3);
+}
+
+#[test]
+fn summaries() {
+	let paths = replays();
+	let mut buf = vec![];
+	let report = batch::write_summaries(&mut buf, &paths, Some(&opts())).unwrap();
+	assert_eq!(report.games + report.failed.len(), paths.len());
+	assert!(report
+		.failed
+		.iter()
+		.any(|(p, _)| p.ends_with("corrupt.slp")));
+
+	let mut r = Cursor::new(buf);
+	let metadata = read_file_metadata(&mut r).unwrap();
+	let fields: Vec<_> = metadata
+		.schema
+		.fields
+		.iter()
+		.map(|f| f.name.as_str())
+		.collect();
+	assert_eq!(&fields[..4], &["game", "path", "hash", "slippi_version"]);
+
+	let chunks: Vec<_> = FileReader::new(r, metadata, None, None)
+		.map(|c| c.unwrap())
+		.collect();
+	assert_eq!(chunks.iter().map(|c| c.len()).sum::<usize>(), report.games);
+
+	let chunk = &chunks[0];
+	let ids = chunk.arrays()[0]
+		.as_any()
+		.downcast_ref::<PrimitiveArray<u32>>()
+		.unwrap();
+	let versions = chunk.arrays()[3]
+		.as_any()
+		.downcast_ref::<Utf8Array<i32>>()
+		.unwrap();
+	for (id, version) in ids.values_iter().zip(versions.values_iter()) {
+		let expected = common::read_game(&paths[*id as usize], true).unwrap();
+		assert_eq!(version, expected.start.slippi.version.to_string());
+	}
+}
+
+/// Reads the record batches of an Arrow IPC file, as `(game, frames)`.
+fn read_frames(path: &Path, version: Version) -> Vec<(Vec<u32>, Frame)> {
+	let mut r = File::open(path).unwrap();
+	let metadata = read_file_metadata(&mut r).unwrap();
+	FileReader::new(r, metadata, None, None)
+		.map(|chunk| {
+			let chunk = chunk.unwrap();
+			let ids = chunk.arrays()[0]
+				.as_any()
+				.downcast_ref::<PrimitiveArray<u32>>()
+				.unwrap();
+			let frames = Frame::from_struct_array(
+				chunk.arrays()[1]
+					.as_any()
+					.downcast_ref::<StructArray>()
+					.unwrap()
+					.clone(),
+				version,
+			);
+			(ids.values().to_vec(), frames)
+		})
+		.collect()
+}
+
+#[test]
+fn frames() {
+	let dir = temp_dir("frames");
+	let slpp_path = dir.join("game.slpp");
+	slpp::write(File::create(&slpp_path).unwrap(), game("game"), None).unwrap();
+	// `v3.12` has a different frame schema, so it goes in a separate file
+	let paths = vec![get_path("game"), get_path("v3.12"), slpp_path];
+
+	let mut files = vec![];
+	let report = batch::write_frames(
+		|n| {
+			let path = dir.join(format!("frames-{}.arrow", n));
+			files.push(path.clone());
+			Ok(File::create(path)?)
+		},
+		&paths,
+		Some(&opts()),
+	)
+	.unwrap();
+	assert_eq!(report.games, 3);
+	assert!(report.failed.is_empty());
+	assert_eq!(files.len(), 2);
+
+	// one file per schema, each with a record batch per game
+	for (path, (name, ids)) in files.iter().zip([("game", vec![0, 2]), ("v3.12", vec![1])]) {
+		let expected = game(name);
+		let version = expected.start.slippi.version;
+		let chunks = read_frames(path, version);
+		assert_eq!(chunks.iter().map(|(g, _)| g[0]).collect::<Vec<_>>(), ids);
+		for (game_ids, frames) in &chunks {
+			assert!(game_ids.iter().all(|x| *x == game_ids[0]));
+			assert_eq!(frames.len(), expected.len());
+			for idx in [0, frames.len() / 2, frames.len() - 1] {
+				assert_eq!(
+					frames.transpose_one(idx, version),
+					expected.frames.transpose_one(idx, version)
+				);
+			}
+		}
+	}
+}
@@ -0,0 +1,62 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	game::{immutable::Game, Game as _},
+	io::slippi::de::{parse_event, parse_header, parse_start, Event, ParseState},
+};
+
+mod common;
+use common::{assert_game_eq, get_path};
+
+/// Parses `name` until at least `offset` bytes of events have been read, then checkpoints,
+/// restores, and parses the rest.
+fn resume(name: &str, offset: usize) -> Game {
+	let bytes = fs::read(get_path(name)).unwrap();
+	let mut r = &bytes[..];
+	parse_header(&mut r, None).unwrap();
+	let raw = r;
+
+	let mut state = parse_start(&mut r, None).unwrap();
+	let mut ended = false;
+	while !ended && state.bytes_read() < offset {
+		ended = parse_event(&mut r, &mut state, None).unwrap() == Event::GameEnd as u8;
+	}
+
+	let mut buf = vec![];
+	state.checkpoint(&mut buf).unwrap();
+	let closed = state.closed_frames();
+	drop(state);
+
+	let mut state = ParseState::restore(&buf[..], None).unwrap();
+	assert_eq!(state.frames().len(), closed);
+	assert_eq!(state.closed_frames(), closed);
+
+	let mut r = &raw[state.bytes_read()..];
+	while state.end().is_none() {
+		parse_event(&mut r, &mut state, None).unwrap();
+	}
+	Game::from(state)
+}
+
+#[test]
+fn checkpoint() {
+	for name in ["game", "ics2", "items", "v0.1", "v2.0", "v3.12", "v3.18"] {
+		let len = fs::read(get_path(name)).unwrap().len();
+		for offset in [0, 1000, len / 3, len / 2 + 7, len] {
+			assert_game_eq(&resume(name, offset), name);
+		}
+	}
+}
+
+#[test]
+fn mid_gecko_codes() {
+	// v3.12's Gecko Codes are split across many Message Splitter events
+	assert_game_eq(&resume("v3.12", 2000), "v3.12");
+}
+
+#[test]
+fn invalid() {
+	assert!(ParseState::restore(&b"not a checkpoint"[..], None).is_err());
+}
@@ -1,11 +1,16 @@
+// Shared by all test crates, none of which uses every helper.
+#![allow(dead_code)]
+
 use std::{
-	fs::File,
+	fs::{self, File},
 	io::BufReader,
 	path::{Path, PathBuf},
 };
 
+use pretty_assertions::assert_eq;
+
 use peppi::{
-	game::immutable::Game,
+	game::{immutable::Game, Game as _},
 	io::{slippi, Result},
 };
 
@@ -27,3 +32,33 @@ pub fn get_path(name: &str) -> PathBuf {
 pub fn game(name: &str) -> Game {
 	read_game(get_path(name), false).unwrap()
 }
+
+/// All test replays, sorted by path. Includes the deliberately broken ones.
+pub fn replays() -> Vec<PathBuf> {
+	let mut paths: Vec<_> = fs::read_dir("tests/data")
+		.unwrap()
+		.map(|e| e.unwrap().path())
+		.collect();
+	paths.sort();
+	paths
+}
+
+/// Creates an empty scratch directory, `peppi-{name}-{pid}` under the system temp dir.
+pub fn temp_dir(name: &str) -> PathBuf {
+	let dir = std::env::temp_dir().join(format!("peppi-{}-{}", name, std::process::id()));
+	let _ = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir).unwrap();
+	dir
+}
+
+/// Asserts that `actual` matches the test replay `name`, frame by frame.
+pub fn assert_game_eq(actual: &Game, name: &str) {
+	let expected = game(name);
+	assert_eq!(actual.start, expected.start);
+	assert_eq!(actual.end, expected.end);
+	assert_eq!(actual.gecko_codes, expected.gecko_codes);
+	assert_eq!(actual.len(), expected.len());
+	for idx in 0..actual.len() {
+		assert_eq!(actual.frame(idx), expected.frame(idx));
+	}
+}
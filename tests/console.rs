@@ -0,0 +1,302 @@
+use std::{
+	fs,
+	io::BufWriter,
+	net::{TcpListener, TcpStream},
+	thread::{self, JoinHandle},
+};
+
+use pretty_assertions::assert_eq;
+
+use peppi::io::slippi::console::{Connection, Message, Update};
+
+mod common;
+use common::{assert_game_eq, game, get_path};
+
+/// Raw event data of a replay (the contents of its `raw` element).
+fn raw(name: &str) -> Vec<u8> {
+	let bytes = fs::read(get_path(name)).unwrap();
+	let len = u32::from_be_bytes(bytes[11..15].try_into().unwrap()) as usize;
+	bytes[15..15 + len].to_vec()
+}
+
+/// How a mock console connection should behave.
+struct Session {
+	/// Position of `data[0]`.
+	base: u64,
+	/// Stop sending (and close the connection) once this position is reached.
+	stop_at: Option<u64>,
+	/// Skip ahead to this position (with `forcePos`) before sending.
+	skip_to: Option<u64>,
+}
+
+/// Serves `data` to one client per session, in uneven chunks with keep-alives mixed in.
+fn serve(data: Vec<u8>, sessions: Vec<Session>) -> (u16, JoinHandle<Vec<Message>>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let port = listener.local_addr().unwrap().port();
+	let handle = thread::spawn(move || {
+		let mut handshakes = vec![];
+		for session in sessions {
+			let (mut stream, _) = listener.accept().unwrap();
+			let handshake = Message::read(&mut stream).unwrap().unwrap();
+			let Message::HandshakeRequest { cursor, .. } = handshake else {
+				panic!("expected handshake, but got: {:?}", handshake);
+			};
+			handshakes.push(handshake.clone());
+
+			let mut pos = match cursor {
+				0 => session.base,
+				c => c,
+			};
+			let mut w = BufWriter::new(&mut stream);
+			Message::HandshakeResponse {
+				nick: "Mock".to_string(),
+				nintendont_version: "1.11.1".to_string(),
+				client_token: 0xDEADBEEF,
+				pos,
+			}
+			.write(&mut w)
+			.unwrap();
+
+			let mut force_pos = false;
+			if let Some(skip_to) = session.skip_to {
+				pos = skip_to;
+				force_pos = true;
+			}
+			let end = session.stop_at.unwrap_or(session.base + data.len() as u64);
+			for (i, size) in [1u64, 77, 4096, 3, 1000].iter().cycle().enumerate() {
+				if pos >= end {
+					break;
+				}
+				if i % 3 == 0 {
+					Message::KeepAlive.write(&mut w).unwrap();
+				}
+				let next_pos = (pos + size).min(end);
+				Message::Replay {
+					pos,
+					next_pos,
+					force_pos,
+					data: data[(pos - session.base) as usize..(next_pos - session.base) as usize]
+						.to_vec(),
+				}
+				.write(&mut w)
+				.unwrap();
+				force_pos = false;
+				pos = next_pos;
+			}
+		}
+		handshakes
+	});
+	(port, handle)
+}
+
+fn connect(port: u16) -> Connection {
+	Connection::connect(("127.0.0.1", port), None).unwrap()
+}
+
+#[test]
+fn message_round_trip() {
+	let messages = [
+		Message::HandshakeRequest {
+			cursor: 0x0102030405060708,
+			client_token: 42,
+			is_realtime: true,
+		},
+		Message::HandshakeResponse {
+			nick: "Wii".to_string(),
+			nintendont_version: "1.9.0".to_string(),
+			client_token: 42,
+			pos: 1234,
+		},
+		Message::Replay {
+			pos: 1,
+			next_pos: 4,
+			force_pos: false,
+			data: vec![0x35, 0x00, 0xFF],
+		},
+		Message::KeepAlive,
+	];
+	for m in messages {
+		let mut buf = vec![];
+		m.write(&mut buf).unwrap();
+		assert_eq!(Message::read(&*buf).unwrap(), Some(m));
+	}
+	assert_eq!(Message::read(&[][..]).unwrap(), None);
+}
+
+#[test]
+fn oversized_length() {
+	let len = (1u64 << 60).to_be_bytes();
+	// `[$U#L`: a byte array claiming 2^60 elements
+	let array = [&b"[$U#L"[..], &len].concat();
+	assert!(Message::decode(&array).is_err());
+	// `{U\x04typeSL`: a string claiming 2^60 bytes
+	let string = [&b"{U\x04typeSL"[..], &len].concat();
+	assert!(Message::decode(&string).is_err());
+	// `[$Z#L`: payload-less elements
+	let nulls = [&b"[$Z#L"[..], &len].concat();
+	assert!(Message::decode(&nulls).is_err());
+}
+
+#[test]
+fn games() {
+	let mut data = raw("game");
+	data.extend(raw("v3.12"));
+	let (port, server) = serve(
+		data,
+		vec![Session {
+			base: 1000,
+			stop_at: None,
+			skip_to: None,
+		}],
+	);
+
+	let mut conn = connect(port);
+	assert_eq!(conn.nick(), "Mock");
+	assert_eq!(conn.nintendont_version(), "1.11.1");
+	assert_eq!(conn.client_token(), 0xDEADBEEF);
+	assert_eq!(conn.cursor(), 1000);
+
+	let mut starts = 0;
+	let mut games = vec![];
+	for update in &mut conn {
+		match update.unwrap() {
+			Update::Start => starts += 1,
+			Update::Events(codes) => assert!(!codes.is_empty()),
+			Update::End(game) => games.push(game),
+			Update::Abandoned(_) => panic!("unexpected abandonment"),
+		}
+	}
+	assert_eq!(starts, 2);
+	assert_eq!(games.len(), 2);
+	assert_game_eq(&games[0], "game");
+	assert_game_eq(&games[1], "v3.12");
+	assert!(conn.state().is_none());
+
+	let handshakes = server.join().unwrap();
+	assert_eq!(
+		handshakes,
+		vec![Message::HandshakeRequest {
+			cursor: 0,
+			client_token: 0,
+			is_realtime: false,
+		}]
+	);
+}
+
+#[test]
+fn resume() {
+	let data = raw("game");
+	let half = data.len() as u64 / 2;
+	let (port, server) = serve(
+		data,
+		vec![
+			Session {
+				base: 0,
+				stop_at: Some(half),
+				skip_to: None,
+			},
+			Session {
+				base: 0,
+				stop_at: None,
+				skip_to: None,
+			},
+		],
+	);
+
+	let mut conn = connect(port);
+	let mut games = vec![];
+	loop {
+		match conn.next() {
+			Some(Ok(Update::End(game))) => games.push(game),
+			Some(Ok(_)) => {}
+			Some(Err(e)) => panic!("{}", e),
+			None if games.is_empty() => conn.reconnect().unwrap(),
+			None => break,
+		}
+	}
+	assert_eq!(games.len(), 1);
+	assert_game_eq(&games[0], "game");
+
+	let handshakes = server.join().unwrap();
+	assert_eq!(
+		handshakes[1],
+		Message::HandshakeRequest {
+			cursor: half,
+			client_token: 0xDEADBEEF,
+			is_realtime: false,
+		}
+	);
+}
+
+#[test]
+fn skipped() {
+	let game_len = raw("game").len() as u64;
+	let mut data = raw("game");
+	data.extend(raw("v3.12"));
+	let (port, server) = serve(
+		data,
+		vec![
+			Session {
+				base: 0,
+				stop_at: Some(1000),
+				skip_to: None,
+			},
+			// resume well into the first game, with `forcePos` set
+			Session {
+				base: 0,
+				stop_at: None,
+				skip_to: Some(game_len / 2),
+			},
+		],
+	);
+
+	let mut conn = connect(port);
+	let mut abandoned = vec![];
+	let mut games = vec![];
+	loop {
+		match conn.next() {
+			Some(Ok(Update::End(game))) => games.push(game),
+			Some(Ok(Update::Abandoned(game))) => abandoned.push(game),
+			Some(Ok(_)) => {}
+			Some(Err(e)) => panic!("{}", e),
+			None if abandoned.is_empty() => conn.reconnect().unwrap(),
+			None => break,
+		}
+	}
+	assert_eq!(abandoned.len(), 1);
+	assert_eq!(abandoned[0].start, game("game").start);
+	assert_eq!(games.len(), 1);
+	assert_game_eq(&games[0], "v3.12");
+
+	server.join().unwrap();
+}
+
+#[test]
+fn unexpected_position() {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let port = listener.local_addr().unwrap().port();
+	let server = thread::spawn(move || {
+		let (mut stream, _): (TcpStream, _) = listener.accept().unwrap();
+		Message::read(&mut stream).unwrap();
+		for m in [
+			Message::HandshakeResponse {
+				nick: "Mock".to_string(),
+				nintendont_version: "1.11.1".to_string(),
+				client_token: 1,
+				pos: 10,
+			},
+			Message::Replay {
+				pos: 20,
+				next_pos: 21,
+				force_pos: false,
+				data: vec![0x35],
+			},
+		] {
+			m.write(&mut stream).unwrap();
+		}
+	});
+
+	let mut conn = connect(port);
+	assert!(conn.recv().is_err());
+	server.join().unwrap();
+}
@@ -0,0 +1,303 @@
+use std::collections::HashSet;
+
+use num_enum::TryFromPrimitive;
+use pretty_assertions::assert_eq;
+use ssbm_data::{action_state::*, character::Internal, stage::Stage};
+
+use peppi::{
+	frame::Rollbacks,
+	game::{
+		features::{self, Bounds, Features, Opts},
+		Port,
+	},
+	io::Error,
+};
+
+mod common;
+use common::game;
+
+fn extract(name: &str, opts: &Opts) -> Features {
+	features::extract(&game(name), Some(opts)).unwrap()
+}
+
+fn get(features: &Features, sample: usize, name: &str) -> f32 {
+	let idx = features.names.iter().position(|n| n == name).unwrap();
+	features.sample(sample)[idx]
+}
+
+/// Names of the `prefix_{n}` one-hot features that are set.
+fn hot(features: &Features, sample: usize, prefix: &str) -> Vec<String> {
+	features
+		.names
+		.iter()
+		.zip(features.sample(sample))
+		.filter(|(n, x)| {
+			n.strip_prefix(prefix)
+				.and_then(|n| n.strip_prefix('_'))
+				.is_some_and(|n| n.parse::<usize>().is_ok())
+				&& **x != 0.0
+		})
+		.map(|(n, _)| n.clone())
+		.collect()
+}
+
+#[test]
+fn one_hot() {
+	let game = game("v3.12");
+	let features = features::extract(&game, None).unwrap();
+	assert_eq!(features.len(), game.frames.len());
+	assert_eq!(features.width(), features.names.len());
+	assert_eq!(features.data.len(), features.len() * features.width());
+	assert_eq!(
+		features.names.iter().collect::<HashSet<_>>().len(),
+		features.names.len()
+	);
+
+	let post = &game.frames.ports[0].leader.post;
+	for i in 0..features.len() {
+		assert_eq!(
+			hot(&features, i, "stage"),
+			vec![format!("stage_{}", game.start.stage)]
+		);
+		assert_eq!(
+			hot(&features, i, "self_character"),
+			vec![format!("self_character_{}", post.character.value(i))]
+		);
+		assert_eq!(
+			hot(&features, i, "self_state"),
+			vec![format!("self_state_{}", post.state.value(i))]
+		);
+		for name in ["self_position_x", "self_position_y", "opponent1_position_x"] {
+			assert!(get(&features, i, name).abs() <= 1.0);
+		}
+	}
+}
+
+#[test]
+fn raw() {
+	let game = game("v3.12");
+	let features = extract(
+		"v3.12",
+		&Opts {
+			port: Some(Port::P2),
+			normalize: false,
+			raw_ids: true,
+			..Default::default()
+		},
+	);
+	assert!(!features.names.iter().any(|n| n.starts_with("stage_")));
+
+	let (this, other) = (&game.frames.ports[1].leader, &game.frames.ports[0].leader);
+	for i in 0..features.len() {
+		assert_eq!(get(&features, i, "stage"), game.start.stage as f32);
+		assert_eq!(
+			get(&features, i, "self_character"),
+			this.post.character.value(i) as f32
+		);
+		assert_eq!(
+			get(&features, i, "self_position_x"),
+			this.post.position.x.value(i)
+		);
+		assert_eq!(
+			get(&features, i, "self_joystick_y"),
+			this.pre.joystick.y.value(i)
+		);
+		assert_eq!(
+			get(&features, i, "opponent1_dx"),
+			other.post.position.x.value(i) - this.post.position.x.value(i)
+		);
+	}
+}
+
+#[test]
+fn relative() {
+	let features = extract("v3.18", &Default::default());
+	for i in 0..features.len() {
+		let dx = get(&features, i, "opponent1_position_x") - get(&features, i, "self_position_x");
+		assert!((get(&features, i, "opponent1_dx") - dx).abs() < 1e-5);
+	}
+
+	let features = extract(
+		"v3.18",
+		&Opts {
+			relative: false,
+			..Default::default()
+		},
+	);
+	assert!(!features.names.iter().any(|n| n.ends_with("_distance")));
+}
+
+#[test]
+fn mirror() {
+	// Yoshi's Story has asymmetric blast zones
+	let features = extract(
+		"v3.16",
+		&Opts {
+			mirror: true,
+			..Default::default()
+		},
+	);
+	let len = features.len() / 2;
+	assert_eq!(features.ids[..len], features.ids[len..]);
+	assert!(features.mirrored[..len].iter().all(|m| !m));
+	assert!(features.mirrored[len..].iter().all(|m| *m));
+
+	let flipped = [
+		"_position_x",
+		"_direction",
+		"_joystick_x",
+		"_cstick_x",
+		"_dx",
+	];
+	for i in 0..len {
+		for (n, name) in features.names.iter().enumerate() {
+			let (x, y) = (features.sample(i)[n], features.sample(len + i)[n]);
+			match flipped.iter().any(|f| name.ends_with(f)) {
+				true => assert!((x + y).abs() < 1e-5, "{}: {} vs {}", name, x, y),
+				false => assert_eq!(x, y, "{}", name),
+			}
+		}
+	}
+}
+
+#[test]
+fn window() {
+	let single = extract("v3.12", &Default::default());
+	let windowed = extract(
+		"v3.12",
+		&Opts {
+			window: 4,
+			stride: 3,
+			..Default::default()
+		},
+	);
+	assert_eq!(windowed.names, single.names);
+	assert_eq!(windowed.width(), 4 * single.width());
+	assert_eq!(windowed.len(), (single.len() - 4) / 3 + 1);
+	for i in 0..windowed.len() {
+		assert_eq!(windowed.ids[i], single.ids[3 * i]);
+		assert_eq!(
+			windowed.sample(i),
+			&single.data[3 * i * single.width()..(3 * i + 4) * single.width()]
+		);
+	}
+}
+
+#[test]
+fn rollbacks() {
+	let features = extract(
+		"ics2",
+		&Opts {
+			rollbacks: Some(Rollbacks::ExceptLast),
+			..Default::default()
+		},
+	);
+	let unique: HashSet<_> = features.ids.iter().collect();
+	assert_eq!(unique.len(), features.len());
+	assert!(features.ids.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn bounds() {
+	let mut game = game("v3.12");
+	game.start.stage = 4; // Princess Peach's Castle
+	let features = features::extract(&game, None).unwrap();
+	let x = game.frames.ports[0].leader.post.position.x.value(0);
+	assert!((get(&features, 0, "self_position_x") - x / Bounds::DEFAULT.right).abs() < 1e-6);
+
+	let bounds = Bounds {
+		left: -100.0,
+		right: 100.0,
+		top: 100.0,
+		bottom: -100.0,
+	};
+	let features = features::extract(
+		&game,
+		Some(&Opts {
+			bounds: Some(bounds),
+			..Default::default()
+		}),
+	)
+	.unwrap();
+	assert!((get(&features, 0, "self_position_x") - x / 100.0).abs() < 1e-6);
+}
+
+#[test]
+fn classes() {
+	fn classes<T: TryFromPrimitive<Primitive = u16>>() -> usize {
+		(0..1024)
+			.filter(|i| T::try_from_primitive(*i).is_ok())
+			.max()
+			.unwrap() as usize
+			+ 1
+	}
+	let features = extract("v3.12", &Default::default());
+	let count = |prefix: &str| {
+		features
+			.names
+			.iter()
+			.filter(|n| {
+				n.strip_prefix(prefix)
+					.is_some_and(|n| n.parse::<usize>().is_ok())
+			})
+			.count()
+	};
+	assert_eq!(count("stage_"), classes::<Stage>());
+	let characters = (0..=u8::MAX)
+		.filter(|i| Internal::try_from(*i).is_ok())
+		.max()
+		.unwrap();
+	assert_eq!(count("self_character_"), characters as usize + 1);
+	let states = [
+		classes::<Common>(),
+		classes::<Bowser>(),
+		classes::<CaptainFalcon>(),
+		classes::<DonkeyKong>(),
+		classes::<DrMario>(),
+		classes::<Falco>(),
+		classes::<Fox>(),
+		classes::<GameAndWatch>(),
+		classes::<Ganondorf>(),
+		classes::<Jigglypuff>(),
+		classes::<Kirby>(),
+		classes::<Link>(),
+		classes::<Luigi>(),
+		classes::<Mario>(),
+		classes::<Marth>(),
+		classes::<Mewtwo>(),
+		classes::<Nana>(),
+		classes::<Ness>(),
+		classes::<Peach>(),
+		classes::<Pichu>(),
+		classes::<Pikachu>(),
+		classes::<Popo>(),
+		classes::<Roy>(),
+		classes::<Samus>(),
+		classes::<Sheik>(),
+		classes::<Yoshi>(),
+		classes::<YoungLink>(),
+		classes::<Zelda>(),
+	];
+	assert_eq!(count("self_state_"), states.into_iter().max().unwrap());
+}
+
+#[test]
+fn no_players() {
+	let mut game = game("v3.12");
+	game.frames.ports.clear();
+	let err = features::extract(&game, None).unwrap_err();
+	assert!(matches!(err, Error::InvalidData(e) if e == "no players"));
+}
+
+#[test]
+fn no_such_port() {
+	let err = features::extract(
+		&game("v3.12"),
+		Some(&Opts {
+			port: Some(Port::P4),
+			..Default::default()
+		}),
+	)
+	.unwrap_err();
+	assert!(matches!(err, Error::InvalidData(e) if e == "no such port: P4"));
+}
@@ -0,0 +1,86 @@
+use arrow2::array::{Array, PrimitiveArray, Utf8Array};
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	frame::PortOccupancy,
+	game::{port_occupancy, Port},
+	io::{ffi, Error},
+};
+
+mod common;
+use common::game;
+
+#[test]
+fn frames() {
+	for name in ["v3.18", "ics2", "items"] {
+		let game = game(name);
+		let version = game.start.slippi.version;
+		let ports = port_occupancy(&game.start);
+		let id = game.frames.id.values().as_ptr();
+
+		let (schema, array) = ffi::export_frames(game.frames, version, &ports);
+		let frames = unsafe { ffi::import_frames(&schema, array, version, &ports) }.unwrap();
+		// no copying
+		assert_eq!(frames.id.values().as_ptr(), id);
+
+		let expected = common::game(name).frames.into_struct_array(version, &ports);
+		assert_eq!(
+			frames.into_struct_array(version, &ports),
+			expected,
+			"{}",
+			name
+		);
+	}
+}
+
+#[test]
+fn frames_schema_mismatch() {
+	let game = game("v3.18");
+	let version = game.start.slippi.version;
+	let ports = port_occupancy(&game.start);
+	let (schema, array) = ffi::export_frames(game.frames, version, &ports);
+	let wrong = [PortOccupancy {
+		port: Port::P1,
+		follower: false,
+	}];
+	let err = unsafe { ffi::import_frames(&schema, array, version, &wrong) }.unwrap_err();
+	assert!(matches!(err, Error::InvalidData(e) if e.starts_with("unexpected frame schema")));
+}
+
+#[test]
+fn summaries() {
+	let names = ["v3.12", "v3.18", "ics2"];
+	let games: Vec<_> = names.iter().map(|n| game(n)).collect();
+	let paths: Vec<_> = names.iter().map(|n| common::get_path(n)).collect();
+	let stream =
+		ffi::export_summaries(&paths.iter().zip(games.iter()).collect::<Vec<_>>()).unwrap();
+
+	let (field, batches) = unsafe { ffi::import_stream(Box::new(stream)) }.unwrap();
+	assert_eq!(field.name, "summary");
+	assert_eq!(batches.len(), 1);
+	let batch = &batches[0];
+	assert_eq!(batch.len(), games.len());
+
+	let column = |name: &str| {
+		let idx = batch.fields().iter().position(|f| f.name == name).unwrap();
+		batch.values()[idx].clone()
+	};
+	let stage = column("stage");
+	let stage = stage
+		.as_any()
+		.downcast_ref::<PrimitiveArray<u16>>()
+		.unwrap();
+	assert_eq!(
+		stage.values().to_vec(),
+		games.iter().map(|g| g.start.stage).collect::<Vec<_>>()
+	);
+	let path = column("path");
+	let path = path.as_any().downcast_ref::<Utf8Array<i32>>().unwrap();
+	assert_eq!(
+		path.values_iter().collect::<Vec<_>>(),
+		paths
+			.iter()
+			.map(|p| p.to_str().unwrap())
+			.collect::<Vec<_>>()
+	);
+}
@@ -0,0 +1,136 @@
+use pretty_assertions::assert_eq;
+
+use peppi::game::{
+	gecko::{code_type, GeckoCode, KnownCode, UcfVersion},
+	GeckoCodes,
+};
+
+mod common;
+use common::game;
+
+fn gecko_codes(name: &str) -> GeckoCodes {
+	game(name).gecko_codes.unwrap()
+}
+
+#[test]
+fn parse() {
+	let gecko = gecko_codes("v3.16");
+	let codes = gecko.codes().unwrap();
+	assert_eq!(codes.len(), 270);
+	assert_eq!(codes[0].code_type(), Some(code_type::WRITE_32));
+	assert_eq!(codes[0].address(), Some(0x8015EE98));
+	assert_eq!(codes[0].payload(), Some(&[0x38, 0x60, 0x00, 0x01][..]));
+	assert_eq!(codes[19].code_type(), Some(code_type::INSERT_ASM));
+	assert_eq!(codes[19].address(), Some(0x801AF6F4));
+	assert_eq!(codes[19].bytes.len(), 56);
+
+	assert_eq!(
+		codes.iter().map(|c| c.bytes.len()).sum::<usize>(),
+		gecko.actual_size as usize
+	);
+
+	// no Gecko codes before v3.3
+	assert!(game("v2.0").gecko_codes.is_none());
+}
+
+#[test]
+fn pointer() {
+	// insert ASM (po), 2 lines of instructions
+	let mut po = vec![0xD2, 0x00, 0x12, 0x34, 0x00, 0x00, 0x00, 0x02];
+	po.extend([0x60, 0x00, 0x00, 0x00].repeat(4));
+	// write string (po), 3 bytes
+	let mut string = vec![0x16, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x03];
+	string.extend([0x41, 0x42, 0x43, 0x00, 0x00, 0x00, 0x00, 0x00]);
+	let end = vec![0xF0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+	let codes = [po, string, end].map(|bytes| GeckoCode { bytes });
+	let parsed = GeckoCodes::from_codes(&codes).codes().unwrap();
+	assert_eq!(parsed, codes);
+	assert_eq!(parsed[0].code_type(), Some(code_type::INSERT_ASM));
+	assert_eq!(parsed[0].uses_pointer(), Some(true));
+	assert_eq!(parsed[0].address(), Some(0x1234));
+	assert_eq!(parsed[1].code_type(), Some(code_type::WRITE_STRING));
+	assert_eq!(parsed[2].code_type(), Some(code_type::END));
+	assert_eq!(parsed[2].uses_pointer(), Some(false));
+}
+
+#[test]
+fn truncated() {
+	let code = GeckoCode {
+		bytes: vec![0xC2, 0x00],
+	};
+	assert_eq!(code.code_type(), Some(code_type::INSERT_ASM));
+	assert_eq!(code.address(), None);
+	assert_eq!(code.payload(), None);
+	assert_eq!(code.known(), None);
+
+	let code = GeckoCode { bytes: vec![] };
+	assert_eq!(code.code_type(), None);
+	assert_eq!(code.known(), None);
+}
+
+#[test]
+fn unknown_type() {
+	let end = vec![0xF0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+	assert!(GeckoCodes::from_codes(&[GeckoCode { bytes: end }])
+		.codes()
+		.is_ok());
+
+	// search codes (0xF6) have a variable length that we don't decode
+	let search = vec![0xF6, 0x00, 0x00, 0x01, 0x80, 0x00, 0x40, 0x00];
+	assert!(GeckoCodes::from_codes(&[GeckoCode { bytes: search }])
+		.codes()
+		.is_err());
+}
+
+#[test]
+fn round_trip() {
+	for name in ["v3.12", "v3.16", "v3.18"] {
+		let gecko = gecko_codes(name);
+		let rebuilt = GeckoCodes::from_codes(&gecko.codes().unwrap());
+		let size = gecko.actual_size as usize;
+		assert_eq!(rebuilt.actual_size, gecko.actual_size);
+		assert_eq!(rebuilt.bytes.len(), gecko.bytes.len());
+		assert_eq!(rebuilt.bytes[..size], gecko.bytes[..size]);
+	}
+}
+
+#[test]
+fn known() {
+	for (name, ucf) in [
+		("v3.12", UcfVersion::V0_8),
+		("v3.16", UcfVersion::V0_84),
+		("v3.18", UcfVersion::V0_84),
+	] {
+		assert_eq!(
+			gecko_codes(name).known().unwrap(),
+			vec![
+				KnownCode::Ucf(ucf),
+				KnownCode::FrozenPokemonStadium,
+				KnownCode::LagReduction,
+				KnownCode::SlippiOnline,
+			],
+			"{}",
+			name
+		);
+	}
+
+	// same injection point as UCF 0.84's dashback, but a different length
+	let mut code = vec![0xC2, 0x0C, 0x9A, 0x44, 0x00, 0x00, 0x00, 0x01];
+	code.extend([0x60, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+	assert_eq!(GeckoCode { bytes: code }.known(), None);
+}
+
+#[test]
+fn diff() {
+	let (a, b) = (gecko_codes("v3.16"), gecko_codes("v3.18"));
+	assert!(a.diff(&a).unwrap().is_empty());
+
+	let diff = a.diff(&b).unwrap();
+	assert!(!diff.is_empty());
+	assert!(diff
+		.added
+		.iter()
+		.any(|c| c.code_type() == Some(code_type::INSERT_ASM) && c.address() == Some(0x80211BF8)));
+	assert_eq!(diff.removed.len(), b.diff(&a).unwrap().added.len());
+}
@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	frame::Rollbacks,
+	game::health::{netplay_health, LagSpike, NetplayHealth, Opts},
+};
+
+mod common;
+use common::game;
+
+#[test]
+fn offline() {
+	assert_eq!(
+		game("game").netplay_health(None),
+		NetplayHealth {
+			frames: 5209,
+			rollbacks: 0,
+			rollback_lengths: BTreeMap::new(),
+			max_rollback: 0,
+			rollback_frames: 0,
+			max_unfinalized: None,
+			lag_spikes: None,
+		}
+	);
+}
+
+#[test]
+fn rollbacks() {
+	for name in ["ics2", "v3.16"] {
+		let game = game(name);
+		let health = game.netplay_health(None);
+		let repeated = game
+			.frames
+			.rollbacks(Rollbacks::ExceptFirst)
+			.into_iter()
+			.filter(|r| *r)
+			.count();
+		assert!(health.rollbacks > 0);
+		assert_eq!(health.rollback_frames, repeated);
+		assert_eq!(health.frames, game.frames.len() - repeated);
+		assert_eq!(
+			health.rollbacks,
+			health.rollback_lengths.values().sum::<usize>()
+		);
+	}
+}
+
+#[test]
+fn lag_spikes() {
+	let health = game("v3.16").netplay_health(None);
+	assert_eq!(health.max_unfinalized, Some(7));
+	assert!(!health.lag_spikes.unwrap().is_empty());
+
+	let health = game("ics2").netplay_health(None);
+	assert_eq!(health.max_unfinalized, Some(1));
+	assert_eq!(health.lag_spikes, Some(vec![]));
+}
+
+#[test]
+fn synthetic() {
+	let ids = [
+		-123, -122, -121, -120, -121, -120, -119, -118, -116, -117, -116, -115,
+	];
+	let finalized = [
+		-123, -123, -123, -125, -125, -125, -125, -125, -125, -124, -124, -116,
+	];
+	let health = netplay_health(
+		&ids,
+		Some(&finalized),
+		Some(&Opts {
+			lag_spike_threshold: 5,
+		}),
+	);
+	assert_eq!(
+		health,
+		NetplayHealth {
+			frames: 9,
+			rollbacks: 2,
+			rollback_lengths: BTreeMap::from([(2, 2)]),
+			max_rollback: 2,
+			rollback_frames: 3,
+			max_unfinalized: Some(9),
+			lag_spikes: Some(vec![
+				LagSpike {
+					start: -120,
+					end: -120,
+					max_unfinalized: 5,
+				},
+				LagSpike {
+					start: -120,
+					end: -116,
+					max_unfinalized: 9,
+				},
+			]),
+		}
+	);
+}
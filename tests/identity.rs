@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use pretty_assertions::assert_eq;
+
+use peppi::game::identity::{ConnectCode, Suid};
+
+mod common;
+use common::game;
+
+#[test]
+fn connect_code() {
+	let game = game("v3.12");
+	let p1 = game.start.players[0].connect_code().unwrap();
+	let p2 = game.start.players[1].connect_code().unwrap();
+	assert_eq!(p1.as_str(), "XX#111");
+	assert_eq!((p1.tag(), p1.number()), ("XX", "111"));
+	assert_eq!(p2.to_string(), "YYYY#222");
+	assert!(p1 < p2);
+
+	// full-width separator & lower case normalize to the same code
+	let codes: HashSet<ConnectCode> = ["xx＃111", "XX#111", "Xx＃111"]
+		.iter()
+		.map(|s| s.parse().unwrap())
+		.collect();
+	assert_eq!(codes, HashSet::from([p1]));
+
+	for invalid in ["", "XX", "#111", "XX#", "XX#1a", "X-X#1", "ABCDEFG#123"] {
+		assert!(invalid.parse::<ConnectCode>().is_err(), "{}", invalid);
+	}
+
+	// offline games have no connect codes
+	assert_eq!(self::game("game").start.players[0].connect_code(), None);
+}
+
+#[test]
+fn suid() {
+	let game = game("v3.12");
+	assert_eq!(
+		game.start.players[0].suid(),
+		Some("aaaaaaaaaaaaaaaaaaaaaaaaaaaa".parse::<Suid>().unwrap())
+	);
+	assert_eq!(
+		game.start.players[1].suid().unwrap().as_str(),
+		"bbbbbbbbbbbbbbbbbbbbbbbbbbbb"
+	);
+	assert!("".parse::<Suid>().is_err());
+	assert!("a b".parse::<Suid>().is_err());
+
+	// added in v3.11
+	assert_eq!(self::game("netplay").start.players[0].suid(), None);
+}
+
+#[test]
+fn serde() {
+	let code: ConnectCode = "ab＃12".parse().unwrap();
+	assert_eq!(serde_json::to_string(&code).unwrap(), r#""AB#12""#);
+	assert_eq!(
+		serde_json::from_str::<ConnectCode>(r#""ab#12""#).unwrap(),
+		code
+	);
+	assert!(serde_json::from_str::<ConnectCode>(r#""ab12""#).is_err());
+}
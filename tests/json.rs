@@ -1,5 +1,16 @@
+use std::{fs, io::Cursor};
+
 use pretty_assertions::assert_eq;
-use serde_json::{from_str, json, to_string, Value};
+use serde_json::{from_slice, from_str, json, to_string, Value};
+
+use peppi::io::{
+	json::{
+		self as io_json,
+		de::Opts as ReadOpts,
+		ser::{Frames, Opts},
+	},
+	slippi,
+};
 
 mod common;
 use common::game;
@@ -134,3 +145,97 @@ fn json_end() {
 		})
 	);
 }
+
+fn write_json(name: &str, frames: Frames) -> Vec<u8> {
+	let mut buf = vec![];
+	io_json::write(&mut buf, game(name), Some(&Opts { frames })).unwrap();
+	buf
+}
+
+#[test]
+fn round_trip() {
+	for entry in fs::read_dir("tests/data").unwrap().map(|e| e.unwrap()) {
+		let name = entry.file_name().into_string().unwrap();
+		if name == "unknown_event.slp" || name == "corrupt.slp" {
+			continue;
+		}
+		// rows are slow to (de)serialize, so only check them for a few replays
+		let layouts = match name.as_str() {
+			"items.slp" | "v3.18.slp" => &[Frames::Columns, Frames::Rows][..],
+			_ => &[Frames::Columns],
+		};
+		let bytes1 = fs::read(entry.path()).unwrap();
+		for frames in layouts {
+			let mut buf = vec![];
+			let game1 = slippi::read(Cursor::new(&bytes1), None).unwrap();
+			io_json::write(&mut buf, game1, Some(&Opts { frames: *frames })).unwrap();
+			let game2 = io_json::read(&*buf, None).unwrap();
+
+			let mut bytes2 = Vec::with_capacity(bytes1.len());
+			slippi::write(&mut bytes2, &game2).unwrap();
+			assert!(bytes1 == bytes2, "{} ({:?})", name, frames);
+		}
+	}
+}
+
+#[test]
+fn frame_layout() {
+	let expected = game("ics2");
+	let port = expected.frames.ports[0].port.to_string();
+	let nana = expected.frames.ports[0]
+		.follower
+		.as_ref()
+		.unwrap()
+		.validity
+		.as_ref()
+		.unwrap();
+	let absent = (0..nana.len()).position(|i| !nana.get_bit(i)).unwrap();
+
+	let columns = &from_slice::<Value>(&write_json("ics2", Frames::Columns)).unwrap()["frames"];
+	assert_eq!(
+		columns["id"].as_array().unwrap().len(),
+		expected.frames.len()
+	);
+	assert_eq!(
+		columns["ports"][&port]["leader"]["post"]["position"]["x"][0]
+			.as_f64()
+			.unwrap() as f32,
+		expected.frames.ports[0].leader.post.position.x.value(0)
+	);
+	assert_eq!(
+		columns["ports"][&port]["follower"]["validity"][absent],
+		json!(false)
+	);
+	assert_eq!(
+		columns["item"]["offsets"].as_array().unwrap().len(),
+		expected.frames.len() + 1
+	);
+
+	let rows = from_slice::<Value>(&write_json("ics2", Frames::Rows)).unwrap();
+	let rows = rows["frames"].as_array().unwrap();
+	assert_eq!(rows.len(), expected.frames.len());
+	assert_eq!(rows[0]["id"], json!(expected.frames.id.value(0)));
+	assert_eq!(
+		rows[0]["ports"][&port]["leader"]["post"]["position"]["x"]
+			.as_f64()
+			.unwrap() as f32,
+		expected.frames.ports[0].leader.post.position.x.value(0)
+	);
+	assert_eq!(rows[absent]["ports"][&port]["follower"], Value::Null);
+	assert!(rows[0]["item"].is_array());
+}
+
+#[test]
+fn skip_frames() {
+	let expected = game("v3.12");
+	let game = io_json::read(
+		&*write_json("v3.12", Frames::Columns),
+		Some(&ReadOpts { skip_frames: true }),
+	)
+	.unwrap();
+	assert_eq!(game.frames.len(), 0);
+	assert_eq!(game.start, expected.start);
+	assert_eq!(game.end, expected.end);
+	assert_eq!(game.metadata, expected.metadata);
+	assert_eq!(game.gecko_codes, expected.gecko_codes);
+}
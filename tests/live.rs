@@ -0,0 +1,133 @@
+use std::{
+	fs::{self, File, OpenOptions},
+	io::Write,
+	path::PathBuf,
+};
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	game::Game as _,
+	io::slippi::{
+		de::Event,
+		live::{Follower, Opts, Tail, Update},
+	},
+};
+
+mod common;
+use common::{game, get_path, temp_dir};
+
+/// Appends `bytes` to `path` in uneven chunks, calling `f` after each one.
+fn write_chunked(path: &PathBuf, bytes: &[u8], mut f: impl FnMut()) {
+	let mut file = OpenOptions::new().append(true).open(path).unwrap();
+	let mut pos = 0;
+	for size in [3, 20, 500, 1, 7777].iter().cycle() {
+		let end = (pos + size).min(bytes.len());
+		file.write_all(&bytes[pos..end]).unwrap();
+		file.flush().unwrap();
+		f();
+		pos = end;
+		if pos == bytes.len() {
+			break;
+		}
+	}
+}
+
+#[test]
+fn tail() {
+	let dir = temp_dir("tail");
+	let path = dir.join("game.slp");
+	File::create(&path).unwrap();
+
+	let mut tail = Tail::open(&path).unwrap();
+	let mut codes = vec![];
+	let bytes = fs::read(get_path("game")).unwrap();
+	write_chunked(&path, &bytes, || codes.extend(tail.poll(None).unwrap()));
+
+	assert!(tail.is_ended());
+	assert_eq!(codes.first(), Some(&(Event::GameStart as u8)));
+	assert_eq!(codes.last(), Some(&(Event::GameEnd as u8)));
+
+	let expected = game("game");
+	let state = tail.into_state().unwrap();
+	assert_eq!(state.start(), &expected.start);
+	assert_eq!(state.end(), &expected.end);
+	assert_eq!(state.len(), expected.frames.len());
+	assert_eq!(state.frame(100), expected.frame(100));
+
+	fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn follower() {
+	let dir = temp_dir("follower");
+	fs::copy(get_path("v3.12"), dir.join("old.slp")).unwrap();
+	fs::write(dir.join("notes.txt"), "not a replay").unwrap();
+
+	let mut follower = Follower::new(&dir, Some(&Opts::default())).unwrap();
+	assert!(follower.poll().unwrap().is_empty()); // existing replays are ignored
+
+	let mut updates = vec![];
+	for name in ["game", "v3.16"] {
+		let path = dir.join(format!("{}.slp", name));
+		File::create(&path).unwrap();
+		let bytes = fs::read(get_path(name)).unwrap();
+		write_chunked(&path, &bytes, || updates.extend(follower.poll().unwrap()));
+	}
+	updates.extend(follower.poll().unwrap());
+
+	let summary: Vec<_> = updates
+		.iter()
+		.filter_map(|u| match u {
+			Update::Start { path } => Some(("start", path.file_name().unwrap().to_owned())),
+			Update::End { path, .. } => Some(("end", path.file_name().unwrap().to_owned())),
+			Update::Abandoned { path, .. } => {
+				Some(("abandoned", path.file_name().unwrap().to_owned()))
+			}
+			Update::Events { .. } => None,
+		})
+		.collect();
+	assert_eq!(
+		summary,
+		vec![
+			("start", "game.slp".into()),
+			("end", "game.slp".into()),
+			("start", "v3.16.slp".into()),
+			("end", "v3.16.slp".into()),
+		]
+	);
+
+	let ended: Vec<_> = updates
+		.into_iter()
+		.filter_map(|u| match u {
+			Update::End { game, .. } => Some(game),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(ended[0].frames.len(), game("game").frames.len());
+	assert_eq!(ended[1].end, game("v3.16").end);
+
+	fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn abandoned() {
+	let dir = temp_dir("abandoned");
+	let mut follower = Follower::new(&dir, None).unwrap();
+
+	let bytes = fs::read(get_path("game")).unwrap();
+	fs::write(dir.join("a.slp"), &bytes[..bytes.len() / 2]).unwrap();
+	assert!(follower.poll().unwrap().len() >= 2);
+	assert!(follower.state().unwrap().len() > 0);
+
+	fs::write(dir.join("b.slp"), &bytes[..10]).unwrap();
+	let updates = follower.poll().unwrap();
+	assert!(matches!(
+		&updates[..],
+		[Update::Abandoned { game: Some(_), .. }]
+	));
+	assert!(follower.path().unwrap().ends_with("b.slp"));
+	assert!(follower.state().is_none());
+
+	fs::remove_dir_all(&dir).unwrap();
+}
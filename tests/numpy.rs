@@ -0,0 +1,238 @@
+use std::{
+	collections::{BTreeMap, HashSet},
+	io::{Cursor, Read},
+};
+
+use arrow2::bitmap::{Bitmap, MutableBitmap};
+use byteorder::{ByteOrder, LittleEndian};
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	frame::Rollbacks,
+	io::{
+		numpy::{self, Opts},
+		Error,
+	},
+};
+
+mod common;
+use common::game;
+
+/// A parsed `.npy`: dtype, shape, and raw data.
+#[derive(Debug)]
+struct Npy {
+	descr: String,
+	len: usize,
+	data: Vec<u8>,
+}
+
+fn parse_npy(buf: &[u8]) -> Npy {
+	assert_eq!(&buf[..8], b"\x93NUMPY\x01\x00");
+	let header_len = LittleEndian::read_u16(&buf[8..10]) as usize;
+	let data_start = 10 + header_len;
+	assert_eq!(data_start % 64, 0);
+	let header = std::str::from_utf8(&buf[10..data_start]).unwrap();
+	assert!(header.ends_with('\n'));
+	let field = |key: &str| {
+		let start = header.find(key).unwrap() + key.len();
+		let end = start + header[start..].find(['\'', ',']).unwrap();
+		header[start..end].to_string()
+	};
+	assert!(header.contains("'fortran_order': False"));
+	Npy {
+		descr: field("'descr': '"),
+		len: field("'shape': (").parse().unwrap(),
+		data: buf[data_start..].to_vec(),
+	}
+}
+
+fn npz(name: &str, opts: &Opts) -> (BTreeMap<String, Npy>, usize) {
+	let mut buf = Cursor::new(vec![]);
+	numpy::write_npz(&mut buf, game(name), Some(opts)).unwrap();
+	let size = buf.get_ref().len();
+	let mut zip = zip::ZipArchive::new(buf).unwrap();
+	let mut arrays = BTreeMap::new();
+	for i in 0..zip.len() {
+		let mut file = zip.by_index(i).unwrap();
+		let name = file.name().strip_suffix(".npy").unwrap().to_string();
+		let mut buf = vec![];
+		file.read_to_end(&mut buf).unwrap();
+		arrays.insert(name, parse_npy(&buf));
+	}
+	(arrays, size)
+}
+
+fn f32s(npy: &Npy) -> Vec<f32> {
+	assert_eq!(npy.descr, "<f4");
+	let mut values = vec![0.0; npy.len];
+	LittleEndian::read_f32_into(&npy.data, &mut values);
+	values
+}
+
+#[test]
+fn fields() {
+	let expected = game("v3.12");
+	let (arrays, _) = npz(
+		"v3.12",
+		&Opts {
+			fields: vec![
+				"pre.joystick".to_string(),
+				"post.position".to_string(),
+				"post.state".to_string(),
+			],
+			..Default::default()
+		},
+	);
+
+	let mut names = vec!["id".to_string()];
+	for p in expected.frames.ports.iter() {
+		let port = p.port.to_string().to_lowercase();
+		for field in [
+			"post_position_x",
+			"post_position_y",
+			"post_state",
+			"pre_joystick_x",
+			"pre_joystick_y",
+		] {
+			names.push(format!("{}_leader_{}", port, field));
+		}
+	}
+	names.sort();
+	assert_eq!(arrays.keys().cloned().collect::<Vec<_>>(), names);
+
+	let id = &arrays["id"];
+	assert_eq!(id.descr, "<i4");
+	assert_eq!(id.len, expected.frames.len());
+
+	let port = expected.frames.ports[0].port.to_string().to_lowercase();
+	let state = &arrays[&format!("{}_leader_post_state", port)];
+	assert_eq!(state.descr, "<u2");
+	assert_eq!(state.data.len(), 2 * expected.frames.len());
+	assert_eq!(
+		f32s(&arrays[&format!("{}_leader_post_position_x", port)]),
+		expected.frames.ports[0]
+			.leader
+			.post
+			.position
+			.x
+			.values()
+			.as_slice()
+	);
+}
+
+#[test]
+fn follower() {
+	let expected = game("ics2");
+	let (arrays, _) = npz(
+		"ics2",
+		&Opts {
+			fields: vec!["post.position.x".to_string()],
+			..Default::default()
+		},
+	);
+	for p in expected.frames.ports.iter() {
+		let port = p.port.to_string().to_lowercase();
+		let Some(follower) = p.follower.as_ref() else {
+			assert!(!arrays.contains_key(&format!("{}_follower_valid", port)));
+			continue;
+		};
+		let validity = follower.validity.as_ref().unwrap();
+		let valid = &arrays[&format!("{}_follower_valid", port)];
+		assert_eq!(valid.descr, "|b1");
+		assert_eq!(
+			valid.data,
+			validity.iter().map(u8::from).collect::<Vec<_>>()
+		);
+		let x = f32s(&arrays[&format!("{}_follower_post_position_x", port)]);
+		for (i, x) in x.iter().enumerate() {
+			match validity.get_bit(i) {
+				true => assert_eq!(*x, follower.post.position.x.value(i)),
+				false => assert!(x.is_nan()),
+			}
+		}
+	}
+}
+
+#[test]
+fn follower_pre_and_post() {
+	// drop a post-frame event that has a matching pre-frame event
+	let mut game = game("ics2");
+	let follower = game.frames.ports[0].follower.as_mut().unwrap();
+	let mut validity: MutableBitmap = follower.validity.as_ref().unwrap().iter().collect();
+	let idx = validity.iter().position(|v| v).unwrap();
+	let pre: Bitmap = validity.clone().into();
+	validity.set(idx, false);
+	follower.post.validity = Some(validity.into());
+
+	let mut buf = Cursor::new(vec![]);
+	let opts = Opts {
+		fields: vec!["pre.joystick.x".to_string(), "post.position.x".to_string()],
+		..Default::default()
+	};
+	numpy::write_npz(&mut buf, game, Some(&opts)).unwrap();
+	let mut zip = zip::ZipArchive::new(buf).unwrap();
+	let mut data = vec![];
+	zip.by_name("p1_follower_valid.npy")
+		.unwrap()
+		.read_to_end(&mut data)
+		.unwrap();
+	let valid = parse_npy(&data);
+	assert_eq!(valid.data[idx], 0);
+	assert_eq!(
+		valid.data.iter().filter(|v| **v != 0).count(),
+		pre.len() - pre.unset_bits() - 1
+	);
+}
+
+#[test]
+fn rollbacks() {
+	let expected = game("ics2");
+	let unique: HashSet<_> = expected.frames.id.values().iter().collect();
+	assert!(unique.len() < expected.frames.len());
+
+	let (arrays, _) = npz(
+		"ics2",
+		&Opts {
+			fields: vec!["post.position".to_string()],
+			rollbacks: Some(Rollbacks::ExceptLast),
+			..Default::default()
+		},
+	);
+	for npy in arrays.values() {
+		assert_eq!(npy.len, unique.len());
+	}
+	let mut ids = vec![0; unique.len()];
+	LittleEndian::read_i32_into(&arrays["id"].data, &mut ids);
+	assert!(ids.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn compress() {
+	let (uncompressed, uncompressed_size) = npz("v3.12", &Default::default());
+	let (compressed, compressed_size) = npz(
+		"v3.12",
+		&Opts {
+			compress: true,
+			..Default::default()
+		},
+	);
+	assert_eq!(
+		compressed.keys().collect::<Vec<_>>(),
+		uncompressed.keys().collect::<Vec<_>>()
+	);
+	assert!(compressed_size < uncompressed_size);
+}
+
+#[test]
+fn unknown_field() {
+	let err = numpy::write_npz(
+		Cursor::new(vec![]),
+		game("v3.12"),
+		Some(&Opts {
+			fields: vec!["post.velocity".to_string()],
+			..Default::default()
+		}),
+	)
+	.unwrap_err();
+	assert!(matches!(err, Error::InvalidData(e) if e == "no such field: post.velocity"));
+}
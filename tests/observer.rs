@@ -0,0 +1,137 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	frame::{transpose, Rollbacks},
+	game::{immutable::Game, Game as _, Port},
+	io::slippi::{
+		de::ParseState,
+		observer::{Driver, Observer},
+	},
+};
+
+mod common;
+use common::{game, get_path};
+
+#[derive(Default)]
+struct Recorder {
+	starts: usize,
+	ends: usize,
+	finalized: Vec<i32>,
+	/// (ID, debug representation) of a sample of finalized frames
+	sampled: Vec<(i32, String)>,
+	rollbacks: Vec<i32>,
+	items: usize,
+	stocks_lost: Vec<(i32, Port, u8)>,
+}
+
+impl Observer for Recorder {
+	fn on_game_start(&mut self, _: &ParseState) {
+		self.starts += 1;
+	}
+
+	fn on_frame_finalized(&mut self, _: &ParseState, frame: &transpose::Frame) {
+		self.finalized.push(frame.id);
+		if self.sampled.len() < 2 || frame.id % 1000 == 0 {
+			self.sampled.push((frame.id, format!("{:?}", frame)));
+		}
+	}
+
+	fn on_rollback(&mut self, _: &ParseState, id: i32) {
+		self.rollbacks.push(id);
+	}
+
+	fn on_item(&mut self, _: &ParseState, _: i32, _: &transpose::Item) {
+		self.items += 1;
+	}
+
+	fn on_stock_lost(&mut self, _: &ParseState, id: i32, port: Port, stocks: u8) {
+		self.stocks_lost.push((id, port, stocks));
+	}
+
+	fn on_game_end(&mut self, _: &ParseState) {
+		self.ends += 1;
+	}
+}
+
+fn observe(name: &str, chunk_size: usize) -> Recorder {
+	let bytes = fs::read(get_path(name)).unwrap();
+	let mut driver = Driver::new(Recorder::default(), None);
+	for chunk in bytes.chunks(chunk_size) {
+		driver.feed(chunk).unwrap();
+	}
+	assert!(driver.is_ended());
+	driver.into_observer()
+}
+
+/// Stock losses computed from the fully-parsed game, keeping only the last copy of each frame.
+fn stocks_lost(game: &Game) -> Vec<(i32, Port, u8)> {
+	let rollbacks = game.frames.rollbacks(Rollbacks::ExceptLast);
+	let mut prev: Vec<_> = game.start.players.iter().map(|p| p.stocks).collect();
+	let mut result = vec![];
+	for i in (0..game.frames.len()).filter(|i| !rollbacks[*i]) {
+		for (p, port) in game.frames.ports.iter().enumerate() {
+			let stocks = port.leader.post.stocks.values()[i];
+			if stocks < prev[p] {
+				result.push((game.frames.id.values()[i], port.port, stocks));
+			}
+			prev[p] = stocks;
+		}
+	}
+	result
+}
+
+#[test]
+fn offline() {
+	let rec = observe("game", 1000);
+	let game = game("game");
+	assert_eq!((rec.starts, rec.ends), (1, 1));
+	assert_eq!(
+		rec.finalized,
+		(-123..-123 + game.frames.len() as i32).collect::<Vec<_>>()
+	);
+	assert_eq!(rec.sampled[0].1, format!("{:?}", game.frame(0)));
+	assert!(rec.rollbacks.is_empty());
+	assert_eq!(rec.stocks_lost, stocks_lost(&game));
+	assert!(!rec.stocks_lost.is_empty());
+}
+
+#[test]
+fn rollbacks() {
+	let rec = observe("ics2", 333);
+	let game = game("ics2");
+	let rollbacks = game.frames.rollbacks(Rollbacks::ExceptLast);
+	let unique = rollbacks.iter().filter(|r| !**r).count();
+
+	// each frame is finalized once, in order
+	assert_eq!(rec.finalized.len(), unique);
+	assert!(rec.finalized.windows(2).all(|w| w[1] == w[0] + 1));
+	assert!(rec.rollbacks.contains(&351));
+
+	// finalized frames have the data from the last copy of each frame
+	for (id, frame) in &rec.sampled {
+		let row = (0..game.frames.len())
+			.rev()
+			.find(|i| game.frames.id.values()[*i] == *id)
+			.unwrap();
+		assert_eq!(frame, &format!("{:?}", game.frame(row)));
+	}
+	assert_eq!(rec.stocks_lost, stocks_lost(&game));
+}
+
+#[test]
+fn items() {
+	let rec = observe("items", 64);
+	let game = game("items");
+	assert_eq!(rec.items, game.frames.item.as_ref().unwrap().id.len());
+}
+
+#[test]
+fn old_version() {
+	// no Frame End events before v3.0
+	let rec = observe("v2.0", 4096);
+	let game = game("v2.0");
+	assert_eq!(rec.finalized.len(), game.frames.len());
+	assert_eq!(rec.stocks_lost, stocks_lost(&game));
+}
@@ -0,0 +1,74 @@
+use pretty_assertions::assert_eq;
+
+use peppi::game::{
+	outcome::{Outcome, PlayerOutcome},
+	EndMethod, Game, Port,
+};
+
+mod common;
+use common::game;
+
+fn winners_losers(name: &str) -> (Vec<Port>, Vec<Port>) {
+	let outcome = game(name).outcome();
+	(outcome.winners, outcome.losers)
+}
+
+#[test]
+fn stocks() {
+	assert_eq!(
+		game("game").outcome(),
+		Outcome {
+			method: Some(EndMethod::Resolved),
+			winners: vec![Port::P1],
+			losers: vec![Port::P2],
+			quitter: None,
+			players: vec![
+				PlayerOutcome {
+					port: Port::P1,
+					stocks: 4,
+					percent: 7.0,
+					placement: None,
+				},
+				PlayerOutcome {
+					port: Port::P2,
+					stocks: 0,
+					percent: 99.590004,
+					placement: None,
+				},
+			],
+		}
+	);
+	assert_eq!(
+		winners_losers("nintendont"),
+		(vec![Port::P4], vec![Port::P2])
+	);
+}
+
+#[test]
+fn placements() {
+	assert_eq!(winners_losers("v3.13"), (vec![Port::P3], vec![Port::P1]));
+	assert_eq!(
+		winners_losers("duplicate_game_end"),
+		(vec![Port::P1], vec![Port::P2])
+	);
+}
+
+#[test]
+fn quit_out() {
+	let outcome = game("v3.16").outcome();
+	assert!(outcome.is_quit_out());
+	assert_eq!(outcome.quitter, Some(Port::P1));
+	assert_eq!(outcome.method, Some(EndMethod::NoContest));
+	assert_eq!(
+		(outcome.winners, outcome.losers),
+		(vec![Port::P2], vec![Port::P1])
+	);
+}
+
+#[test]
+fn unresolved() {
+	let outcome = game("shield_drop").outcome();
+	assert!(!outcome.is_quit_out());
+	assert_eq!(outcome.method, Some(EndMethod::Unresolved));
+	assert_eq!((outcome.winners, outcome.losers), (vec![], vec![]));
+}
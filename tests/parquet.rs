@@ -0,0 +1,223 @@
+#![cfg(feature = "parquet")]
+
+use std::{
+	collections::HashSet,
+	fs::{self, File},
+};
+
+use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use arrow_array::{
+	cast::AsArray,
+	types::{Float32Type, Int32Type},
+	Array, RecordBatch,
+};
+use arrow_schema::DataType;
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	frame::Rollbacks,
+	io::parquet::{self, Compression, Opts, ZstdLevel},
+};
+
+mod common;
+use common::{game, temp_dir};
+
+/// Writes `name`'s frames to a Parquet file, and reads them back as a single batch.
+fn write_read(test: &str, name: &str, opts: &Opts) -> (RecordBatch, u64) {
+	let path = temp_dir(test).join(format!("{}.parquet", name));
+	parquet::write(File::create(&path).unwrap(), game(name), Some(opts)).unwrap();
+	let size = fs::metadata(&path).unwrap().len();
+	let batches = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+		.unwrap()
+		.with_batch_size(usize::MAX)
+		.build()
+		.unwrap()
+		.collect::<Result<Vec<_>, _>>()
+		.unwrap();
+	assert_eq!(batches.len(), 1);
+	(batches.into_iter().next().unwrap(), size)
+}
+
+fn ids(batch: &RecordBatch) -> Vec<i32> {
+	batch
+		.column_by_name("id")
+		.unwrap()
+		.as_primitive::<Int32Type>()
+		.values()
+		.to_vec()
+}
+
+#[test]
+fn nested() {
+	let expected = game("v3.12");
+	let (batch, _) = write_read("nested", "v3.12", &Default::default());
+	assert_eq!(batch.num_rows(), expected.frames.len());
+	assert_eq!(
+		batch
+			.schema()
+			.fields()
+			.iter()
+			.map(|f| f.name().as_str())
+			.collect::<Vec<_>>(),
+		vec!["id", "ports", "start", "end", "item"]
+	);
+	assert_eq!(ids(&batch), expected.frames.id.values().as_slice());
+
+	let port = expected.frames.ports[0].port.to_string();
+	let x = batch.column_by_name("ports").unwrap().as_struct()[port.as_str()].as_struct()["leader"]
+		.as_struct()["post"]
+		.as_struct()["position"]
+		.as_struct()["x"]
+		.as_primitive::<Float32Type>()
+		.values()
+		.to_vec();
+	assert_eq!(
+		x,
+		expected.frames.ports[0]
+			.leader
+			.post
+			.position
+			.x
+			.values()
+			.as_slice()
+	);
+}
+
+#[test]
+fn nullability() {
+	let expected = game("ics2");
+	let (batch, _) = write_read("nullability", "ics2", &Default::default());
+	let schema = batch.schema();
+	assert!(!schema.field_with_name("id").unwrap().is_nullable());
+	let ports = schema.field_with_name("ports").unwrap();
+	let DataType::Struct(ports) = ports.data_type() else {
+		panic!("ports isn't a struct");
+	};
+	for p in expected.frames.ports.iter() {
+		let (_, port) = ports.find(&p.port.to_string()).unwrap();
+		let DataType::Struct(port) = port.data_type() else {
+			panic!("port isn't a struct");
+		};
+		assert!(!port.find("leader").unwrap().1.is_nullable());
+		if p.follower.is_some() {
+			assert!(port.find("follower").unwrap().1.is_nullable());
+		}
+	}
+
+	let (batch, _) = write_read(
+		"nullability",
+		"ics2",
+		&Opts {
+			flatten: true,
+			..Default::default()
+		},
+	);
+	for field in batch.schema().fields() {
+		assert_eq!(
+			field.is_nullable(),
+			// port names have no underscores, so the second segment is the character
+			field.name().split('_').nth(1) == Some("follower"),
+			"{}",
+			field.name()
+		);
+	}
+}
+
+#[test]
+fn flatten() {
+	let opts = Opts {
+		flatten: true,
+		..Default::default()
+	};
+
+	let expected = game("v3.12");
+	let (batch, _) = write_read("flatten", "v3.12", &opts);
+	assert_eq!(batch.num_rows(), expected.frames.len());
+	let port = expected.frames.ports[0].port;
+	let x = batch
+		.column_by_name(&format!(
+			"{}_leader_post_position_x",
+			port.to_string().to_lowercase()
+		))
+		.unwrap()
+		.as_primitive::<Float32Type>();
+	assert_eq!(
+		x.values().as_ref(),
+		expected.frames.ports[0]
+			.leader
+			.post
+			.position
+			.x
+			.values()
+			.as_slice()
+	);
+	// lists can't be flattened
+	assert!(batch
+		.column_by_name("item")
+		.unwrap()
+		.as_list_opt::<i32>()
+		.is_some());
+
+	// rows where Nana is absent are null in all of her columns
+	let expected = game("ics2");
+	let (batch, _) = write_read("flatten", "ics2", &opts);
+	for p in expected.frames.ports.iter() {
+		let Some(follower) = p.follower.as_ref() else {
+			continue;
+		};
+		let x = batch
+			.column_by_name(&format!(
+				"{}_follower_post_position_x",
+				p.port.to_string().to_lowercase()
+			))
+			.unwrap();
+		assert_eq!(
+			(0..x.len()).map(|i| x.is_valid(i)).collect::<Vec<_>>(),
+			(0..expected.frames.len())
+				.map(|i| follower.validity.as_ref().is_none_or(|v| v.get_bit(i)))
+				.collect::<Vec<_>>(),
+		);
+	}
+}
+
+#[test]
+fn rollbacks() {
+	let expected = game("ics2");
+	let all = expected.frames.id.values();
+	let unique: HashSet<_> = all.iter().collect();
+	assert!(unique.len() < all.len());
+
+	for keep in [Rollbacks::ExceptFirst, Rollbacks::ExceptLast] {
+		let (batch, _) = write_read(
+			"rollbacks",
+			"ics2",
+			&Opts {
+				rollbacks: Some(keep),
+				..Default::default()
+			},
+		);
+		let ids = ids(&batch);
+		assert_eq!(ids.len(), unique.len());
+		assert!(ids.windows(2).all(|w| w[0] < w[1]));
+		let kept: Vec<_> = std::iter::zip(all.iter(), expected.frames.rollbacks(keep))
+			.filter(|(_, r)| !r)
+			.map(|(id, _)| *id)
+			.collect();
+		assert_eq!(ids, kept);
+	}
+}
+
+#[test]
+fn compression() {
+	let (uncompressed, uncompressed_size) = write_read("compression", "v3.18", &Default::default());
+	let (compressed, compressed_size) = write_read(
+		"compression",
+		"v3.18",
+		&Opts {
+			compression: Some(Compression::ZSTD(ZstdLevel::default())),
+			..Default::default()
+		},
+	);
+	assert_eq!(compressed, uncompressed);
+	assert!(compressed_size < uncompressed_size);
+}
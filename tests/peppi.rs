@@ -7,6 +7,7 @@ use ssbm_data::{action_state, character::External, character::Internal, item::It
 
 use peppi::{
 	frame::{
+		item,
 		transpose::{self, Position},
 		Rollbacks,
 	},
@@ -846,6 +847,59 @@ fn items() {
 	);
 }
 
+#[test]
+fn item_misc() {
+	let game = game("items");
+	let items = game
+		.frames
+		.transpose_one(275, game.start.slippi.version)
+		.items
+		.unwrap();
+	assert_eq!(
+		items[0].decoded_misc(),
+		Some(item::Misc::Turnip { face: 0 })
+	);
+
+	let item = game.frames.item.as_ref().unwrap();
+	assert_eq!(item.decoded_misc(0), Some(item::Misc::Turnip { face: 5 }));
+	let faces = item.turnip_face().unwrap();
+	assert_eq!(faces.len(), item.id.len());
+	assert!(faces.iter().all(|f| f.is_some()));
+	assert!(item
+		.charge_shot_power()
+		.unwrap()
+		.iter()
+		.all(|p| p.is_none()));
+
+	for (id, expected) in [
+		(item::SAMUS_CHARGE_SHOT, Item::SamusChargeShot),
+		(item::SAMUS_MISSILE, Item::SamusMissile),
+		(item::PEACH_TURNIP, Item::PeachTurnip),
+		(item::MEWTWO_SHADOW_BALL, Item::MewtwoShadowBall),
+		(
+			item::KIRBY_COPY_MEWTWO_SHADOW_BALL,
+			Item::KirbyCopyMewtwoShadowBall,
+		),
+		(
+			item::KIRBY_COPY_SAMUS_CHARGE_SHOT,
+			Item::KirbyCopySamusChargeShot,
+		),
+	] {
+		assert_eq!(id, expected as u16);
+	}
+
+	assert_eq!(
+		item::Misc::decode(
+			Item::MewtwoShadowBall as u16,
+			transpose::ItemMisc(0, 0, 1, 7)
+		),
+		item::Misc::ChargeShot {
+			launched: true,
+			power: 7
+		}
+	);
+}
+
 fn _round_trip(in_path: impl AsRef<Path> + Clone) {
 	let bytes1 = fs::read(in_path.clone()).unwrap();
 
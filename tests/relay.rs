@@ -0,0 +1,139 @@
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{Read, Write},
+	net::TcpStream,
+	thread::{self, JoinHandle},
+	time::Duration,
+};
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	game::immutable::Game,
+	io::slippi::{
+		de::{parse_event, parse_start, Event},
+		live::Tail,
+		relay::{Opts, Relay},
+	},
+};
+
+mod common;
+use common::{assert_game_eq, game, get_path, temp_dir};
+
+/// Connects to `relay` and reads `count` games from it.
+fn client(relay: &Relay, count: usize) -> JoinHandle<Vec<Game>> {
+	let mut stream = TcpStream::connect(relay.local_addr().unwrap()).unwrap();
+	thread::spawn(move || {
+		(0..count)
+			.map(|_| {
+				let mut state = parse_start(&mut stream, None).unwrap();
+				while parse_event(&mut stream, &mut state, None).unwrap() != Event::GameEnd as u8 {}
+				Game::from(state)
+			})
+			.collect()
+	})
+}
+
+/// Waits until `relay` has accepted `count` clients.
+fn wait_for_clients(relay: &mut Relay, count: usize) {
+	while relay.clients() < count {
+		relay.accept().unwrap();
+		thread::yield_now();
+	}
+}
+
+#[test]
+fn late_join() {
+	let dir = temp_dir("late_join");
+	let path = dir.join("game.slp");
+	File::create(&path).unwrap();
+	let bytes = fs::read(get_path("v3.12")).unwrap();
+
+	let mut relay = Relay::bind("127.0.0.1:0", None).unwrap();
+	let early = client(&relay, 1);
+	wait_for_clients(&mut relay, 1);
+
+	let mut tail = Tail::open(&path).unwrap();
+	let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+	let mut late = None;
+	for (i, chunk) in bytes.chunks(4093).enumerate() {
+		file.write_all(chunk).unwrap();
+		tail.poll(None).unwrap();
+		if let Some(state) = tail.state() {
+			relay.update(state).unwrap();
+			if i == bytes.len() / 4093 * 3 / 4 {
+				assert!(state.frames().len() > 0);
+				late = Some(client(&relay, 1));
+				wait_for_clients(&mut relay, 2);
+			}
+		}
+	}
+	assert!(tail.is_ended());
+
+	// updating again with the ended game doesn't resend it
+	relay.update(tail.state().unwrap()).unwrap();
+
+	for client in [early, late.unwrap()] {
+		let games = client.join().unwrap();
+		assert_eq!(games.len(), 1);
+		assert_game_eq(&games[0], "v3.12");
+	}
+}
+
+#[test]
+fn finish() {
+	let mut relay = Relay::bind("127.0.0.1:0", None).unwrap();
+	let client = client(&relay, 2);
+	wait_for_clients(&mut relay, 1);
+
+	relay.finish(&game("game")).unwrap();
+	relay.finish(&game("game")).unwrap();
+	relay.finish(&game("ics")).unwrap();
+
+	let games = client.join().unwrap();
+	assert_eq!(games.len(), 2);
+	assert_game_eq(&games[0], "game");
+	assert_game_eq(&games[1], "ics");
+}
+
+#[test]
+fn slow_client() {
+	let mut relay = Relay::bind(
+		"127.0.0.1:0",
+		Some(&Opts {
+			write_timeout: Duration::from_secs(600),
+			queue_len: 64,
+		}),
+	)
+	.unwrap();
+	// never reads, so the relay's writes eventually block
+	let _slow = TcpStream::connect(relay.local_addr().unwrap()).unwrap();
+	wait_for_clients(&mut relay, 1);
+	let mut stream = TcpStream::connect(relay.local_addr().unwrap()).unwrap();
+	let fast = thread::spawn(move || {
+		let mut buf = vec![];
+		stream.read_to_end(&mut buf).unwrap();
+		buf
+	});
+	wait_for_clients(&mut relay, 2);
+
+	// alternate games, since the relay won't resend the game that just ended
+	let games = [game("game"), game("ics")];
+	let mut sent = 0;
+	while relay.clients() == 2 {
+		assert!(sent < 1000, "slow client wasn't dropped");
+		relay.finish(&games[sent % 2]).unwrap();
+		sent += 1;
+	}
+
+	// the fast client got everything
+	drop(relay);
+	let buf = fast.join().unwrap();
+	let mut r = buf.as_slice();
+	for i in 0..sent {
+		let mut state = parse_start(&mut r, None).unwrap();
+		while parse_event(&mut r, &mut state, None).unwrap() != Event::GameEnd as u8 {}
+		assert_game_eq(&Game::from(state), ["game", "ics"][i % 2]);
+	}
+	assert!(r.is_empty());
+}
@@ -0,0 +1,187 @@
+use std::{fs, io::Cursor};
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	game::{
+		immutable::Game,
+		remap::{normalize, remap},
+		Port::{self, *},
+	},
+	io::{slippi, Error},
+};
+
+mod common;
+use common::game;
+
+fn write(game: &Game) -> Vec<u8> {
+	let mut buf = vec![];
+	slippi::write(&mut buf, game).unwrap();
+	buf
+}
+
+fn read(bytes: &[u8]) -> Game {
+	slippi::read(Cursor::new(bytes), None).unwrap()
+}
+
+#[test]
+fn swap() {
+	let expected = game("v3.18");
+	let actual = remap(game("v3.18"), &[(P1, P2), (P2, P1)]).unwrap();
+
+	let ports: Vec<_> = actual.start.players.iter().map(|p| p.port).collect();
+	assert_eq!(ports, vec![P1, P2]);
+	for (a, e) in actual
+		.start
+		.players
+		.iter()
+		.zip(expected.start.players.iter().rev())
+	{
+		assert_eq!(a.character, e.character);
+		assert_eq!(a.costume, e.costume);
+		assert_eq!(a.netplay, e.netplay);
+	}
+
+	let (a_end, e_end) = (actual.end.as_ref().unwrap(), expected.end.as_ref().unwrap());
+	for (a, e) in a_end
+		.players
+		.as_ref()
+		.unwrap()
+		.iter()
+		.zip(e_end.players.as_ref().unwrap().iter().rev())
+	{
+		assert_eq!(a.placement, e.placement);
+	}
+
+	let a_meta = actual.metadata.as_ref().unwrap()["players"]
+		.as_object()
+		.unwrap();
+	let e_meta = expected.metadata.as_ref().unwrap()["players"]
+		.as_object()
+		.unwrap();
+	assert_eq!(a_meta["0"], e_meta["1"]);
+	assert_eq!(a_meta["1"], e_meta["0"]);
+
+	for (a, e) in actual
+		.frames
+		.ports
+		.iter()
+		.zip(expected.frames.ports.iter().rev())
+	{
+		assert_eq!(a.leader.post.position.x, e.leader.post.position.x);
+		let last_hit_by: Vec<_> = e
+			.leader
+			.post
+			.last_hit_by
+			.values()
+			.iter()
+			.map(|x| match x {
+				0 => 1,
+				1 => 0,
+				x => *x,
+			})
+			.collect();
+		assert_eq!(a.leader.post.last_hit_by.values().as_slice(), last_hit_by);
+	}
+	assert!(actual.hash.is_none());
+
+	// the remapped game round-trips
+	let bytes = write(&actual);
+	let game = read(&bytes);
+	assert_eq!(game.start, actual.start);
+	assert_eq!(game.end, actual.end);
+	assert_eq!(game.metadata, actual.metadata);
+	assert_eq!(write(&game), bytes);
+}
+
+#[test]
+fn items() {
+	let expected = game("items");
+	let actual = remap(game("items"), &[(P1, P4), (P2, P3)]).unwrap();
+	let owners = |g: &Game| {
+		g.frames
+			.item
+			.as_ref()
+			.unwrap()
+			.owner
+			.as_ref()
+			.unwrap()
+			.values()
+			.to_vec()
+	};
+	let expected_owners: Vec<_> = owners(&expected)
+		.into_iter()
+		.map(|o| match o {
+			0 => 3,
+			1 => 2,
+			o => o,
+		})
+		.collect();
+	assert_eq!(owners(&actual), expected_owners);
+	let ports: Vec<_> = actual.frames.ports.iter().map(|p| p.port).collect();
+	assert_eq!(ports, vec![P3, P4]);
+}
+
+#[test]
+fn there_and_back() {
+	for entry in fs::read_dir("tests/data").unwrap() {
+		let path = entry.unwrap().path();
+		let name = path.file_stem().unwrap().to_str().unwrap();
+		if ["unknown_event", "corrupt"].contains(&name) {
+			continue;
+		}
+		let bytes = fs::read(&path).unwrap();
+		let game = read(&bytes);
+
+		// move everyone to the highest ports, in reverse order
+		let ports: Vec<_> = game
+			.start
+			.players
+			.iter()
+			.enumerate()
+			.map(|(n, p)| (p.port, Port::try_from(3 - n as u8).unwrap()))
+			.collect();
+		let moved = remap(game, &ports).unwrap();
+		for (from, to) in &ports {
+			let character = |g: &Game, port| {
+				g.start
+					.players
+					.iter()
+					.find(|p| p.port == port)
+					.unwrap()
+					.character
+			};
+			assert_eq!(character(&moved, *to), character(&read(&bytes), *from));
+		}
+
+		let inverse: Vec<_> = ports.iter().map(|(from, to)| (*to, *from)).collect();
+		let back = remap(moved, &inverse).unwrap();
+		assert!(write(&back) == bytes, "{}", name);
+	}
+}
+
+#[test]
+fn normalized() {
+	let bytes = fs::read("tests/data/v3.18.slp").unwrap();
+	let moved = remap(read(&bytes), &[(P1, P4), (P2, P3)]).unwrap();
+	let ports: Vec<_> = moved.start.players.iter().map(|p| p.port).collect();
+	assert_eq!(ports, vec![P3, P4]);
+
+	let back = normalize(moved, Some(P4)).unwrap();
+	assert!(write(&back) == bytes);
+}
+
+#[test]
+fn errors() {
+	let err = remap(game("v3.18"), &[(P3, P1)]).unwrap_err();
+	assert!(matches!(err, Error::InvalidData(e) if e == "no player in port: P3"));
+
+	let err = remap(game("v3.18"), &[(P1, P2)]).unwrap_err();
+	assert!(matches!(err, Error::InvalidData(e) if e == "multiple players in port: P2"));
+
+	let err = remap(game("v3.18"), &[(P1, P3), (P1, P4)]).unwrap_err();
+	assert!(matches!(err, Error::InvalidData(e) if e == "port mapped more than once: P1"));
+
+	let err = normalize(game("v3.18"), Some(P4)).unwrap_err();
+	assert!(matches!(err, Error::InvalidData(e) if e == "no player in port: P4"));
+}
@@ -0,0 +1,98 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	frame::Rollbacks,
+	io::slippi::de::{parse_event, parse_header, parse_start, Event},
+};
+
+mod common;
+use common::{game, get_path};
+
+#[test]
+fn live() {
+	for name in ["game", "ics2", "v3.16"] {
+		let expected = game(name);
+		let bytes = fs::read(get_path(name)).unwrap();
+		let mut r = &bytes[..];
+		parse_header(&mut r, None).unwrap();
+		let mut state = parse_start(&mut r, None).unwrap();
+
+		let mut rollbacks = vec![];
+		loop {
+			let len = state.frames().len();
+			let last_id = state.frames().id.values().last().copied();
+			let code = parse_event(&mut r, &mut state, None).unwrap();
+			if let Some(rollback) = state.rollback() {
+				// only the event that opened the re-simulated frame reports the rollback
+				assert_eq!(state.frames().len(), len + 1);
+				assert_eq!(
+					rollback,
+					*state.frames().id.values().last().unwrap()..=last_id.unwrap()
+				);
+				rollbacks.push(rollback);
+			}
+			if code == Event::GameEnd as u8 {
+				break;
+			}
+		}
+		assert_eq!(state.rollback(), None);
+		assert_eq!(
+			rollbacks.len(),
+			expected.netplay_health(None).rollbacks,
+			"{}",
+			name
+		);
+
+		for keep in [Rollbacks::ExceptFirst, Rollbacks::ExceptLast] {
+			assert_eq!(
+				state.frames().rollbacks(keep),
+				expected.frames.rollbacks(keep)
+			);
+			for rollback in &rollbacks {
+				for id in rollback.clone() {
+					assert_eq!(
+						state.frames().index_of(id, keep),
+						expected.frames.index_of(id, keep)
+					);
+				}
+			}
+		}
+	}
+}
+
+#[test]
+fn truncated_after_rollback() {
+	let bytes = fs::read(get_path("ics2")).unwrap();
+	let mut r = &bytes[..];
+	parse_header(&mut r, None).unwrap();
+	let mut state = parse_start(&mut r, None).unwrap();
+	while state.rollback().is_none() {
+		parse_event(&mut r, &mut state, None).unwrap();
+	}
+	let rollback = state.rollback();
+
+	// the next event's code arrives, but not its payload
+	let mut partial = &r[..2];
+	assert!(parse_event(&mut partial, &mut state, None).is_err());
+	assert_eq!(state.rollback(), rollback);
+}
+
+#[test]
+fn index_of() {
+	let game = game("ics2");
+	let rollbacks = game.frames.rollbacks(Rollbacks::ExceptFirst);
+	let idx = rollbacks.iter().position(|r| *r).unwrap();
+	let id = game.frames.id.values()[idx];
+
+	let first = game.frames.index_of(id, Rollbacks::ExceptFirst).unwrap();
+	let last = game.frames.index_of(id, Rollbacks::ExceptLast).unwrap();
+	assert!(first < idx);
+	assert!(last >= idx);
+	assert!(!rollbacks[first]);
+	assert!(!game.frames.rollbacks(Rollbacks::ExceptLast)[last]);
+
+	assert_eq!(game.frames.index_of(-124, Rollbacks::ExceptFirst), None);
+	assert_eq!(game.frames.index_of(-123, Rollbacks::ExceptLast), Some(0));
+}
@@ -0,0 +1,92 @@
+use std::io::Cursor;
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	game::{
+		immutable::Game,
+		rules::{GameMode, ItemSwitch, PlayerFlag, TimerType},
+		Port,
+	},
+	io::{peppi as io_peppi, slippi},
+};
+
+mod common;
+use common::game;
+
+fn slp_round_trip(game: &Game) -> Game {
+	let mut buf = Vec::new();
+	slippi::write(&mut buf, game).unwrap();
+	slippi::read(Cursor::new(buf), None).unwrap()
+}
+
+fn slpp_round_trip(game: Game) -> Game {
+	let mut buf = Vec::new();
+	io_peppi::write(&mut buf, game, Default::default()).unwrap();
+	io_peppi::read(&mut &*buf, None).unwrap()
+}
+
+#[test]
+fn rules() {
+	let game = game("game");
+	let start = &game.start;
+	assert_eq!(start.mode(), Some(GameMode::Stock));
+	assert_eq!(start.timer_type(), Some(TimerType::Decreasing));
+	assert!(start.is_friendly_fire());
+	assert!(!start.is_score_displayed());
+	assert!(start.is_pause_enabled());
+	assert!(!start.is_stamina());
+	assert_eq!(start.enabled_items().len(), 35);
+
+	// Slippi Online disables pausing
+	assert!(!self::game("v3.12").start.is_pause_enabled());
+}
+
+#[test]
+fn player_flags() {
+	let game = game("game");
+	let (p1, p2) = (&game.start.players[0], &game.start.players[1]);
+	assert!(p1.is_rumble());
+	assert!(!p2.is_rumble());
+	for p in [p1, p2] {
+		assert!(!p.is_metal());
+		assert!(!p.is_stamina());
+		assert!(!p.is_low_gravity());
+		assert!(!p.is_invisible());
+	}
+}
+
+#[test]
+fn setters() {
+	let mut game = game("game");
+	let start = &mut game.start;
+	start.set_mode(GameMode::Time);
+	start.set_timer_type(TimerType::Increasing);
+	start.set_friendly_fire(false);
+	start.set_score_displayed(true);
+	start.set_pause_enabled(false);
+	start.set_item_enabled(ItemSwitch::PokeBall, false);
+	start.set_item_enabled(ItemSwitch::Capsule, false);
+	assert!(start.set_player_flag(Port::P2, PlayerFlag::Metal, true));
+	assert!(!start.set_player_flag(Port::P4, PlayerFlag::Metal, true));
+
+	assert_eq!(start.item_spawn_bitfield, [0xFB, 0xFF, 0xFF, 0xFF, 0xFE]);
+	assert_eq!(start.enabled_items().len(), 33);
+
+	let expected = game.start.clone();
+	let slp = slp_round_trip(&game);
+	assert_eq!(slp.start, expected);
+
+	// `.slpp` stores only the raw bytes, so this checks that the setters updated them
+	let slpp = slpp_round_trip(game);
+	assert_eq!(slpp.start, expected);
+	assert_eq!(slpp.start.mode(), Some(GameMode::Time));
+	assert_eq!(slpp.start.timer_type(), Some(TimerType::Increasing));
+	assert!(!slpp.start.is_friendly_fire());
+	assert!(slpp.start.is_score_displayed());
+	assert!(!slpp.start.is_pause_enabled());
+	assert!(!slpp.start.is_item_enabled(ItemSwitch::PokeBall));
+	assert!(slpp.start.is_item_enabled(ItemSwitch::MetalBox));
+	assert!(slpp.start.players[1].is_metal());
+	assert!(!slpp.start.players[0].is_metal());
+}
@@ -0,0 +1,53 @@
+use pretty_assertions::assert_eq;
+
+use peppi::game::{
+	session::{Mode, OnlineMode, Platform, Session},
+	Game,
+};
+
+mod common;
+use common::game;
+
+fn session(name: &str) -> Session {
+	game(name).session()
+}
+
+#[test]
+fn online() {
+	assert_eq!(
+		session("v3.16"),
+		Session {
+			mode: Mode::Online(OnlineMode::Unranked),
+			platform: Some(Platform::Dolphin),
+		}
+	);
+	// scene info, but no match info
+	assert_eq!(session("netplay").mode, Mode::Online(OnlineMode::Unknown));
+	assert!(session("v3.12").is_online());
+}
+
+#[test]
+fn offline() {
+	assert_eq!(
+		session("v3.18"),
+		Session {
+			mode: Mode::Offline,
+			platform: Some(Platform::MainlineDolphin),
+		}
+	);
+	assert_eq!(session("items").mode, Mode::Offline);
+	assert_eq!(session("game").mode, Mode::Unknown);
+}
+
+#[test]
+fn platform() {
+	let nintendont = session("nintendont").platform.unwrap();
+	assert_eq!(nintendont, Platform::Nintendont);
+	assert!(nintendont.is_console() && !nintendont.is_network());
+
+	let network = session("v2.0").platform.unwrap();
+	assert_eq!(network, Platform::Network);
+	assert!(network.is_console() && network.is_network());
+
+	assert_eq!(session("duplicate_game_end").platform, None);
+}
@@ -0,0 +1,58 @@
+use pretty_assertions::assert_eq;
+
+use peppi::game::{
+	set::{sets, PlayerKey, Set, SetGame},
+	Port,
+};
+
+mod common;
+use common::game;
+
+#[test]
+fn match_id() {
+	let games = vec![game("v3.16")];
+	assert_eq!(
+		sets(&games, None),
+		vec![Set {
+			id: Some("mode.unranked-2024-02-15T14:37:23.22-0".to_string()),
+			games: vec![SetGame {
+				index: 0,
+				number: Some(1),
+				tiebreaker: Some(0),
+				winners: Some(vec![Port::P2]),
+			}],
+			missing: vec![],
+			score: vec![
+				(PlayerKey::ConnectCode("SWZ#195".parse().unwrap()), 1),
+				(PlayerKey::ConnectCode("CLWN#889".parse().unwrap()), 0),
+			],
+		}]
+	);
+}
+
+#[test]
+fn timestamps() {
+	// `game` and `unknown_event` share players and a start time, `ics2` and `v3.12` share
+	// connect codes but were played months apart
+	let games = vec![
+		game("ics2"),
+		game("game"),
+		game("v3.12"),
+		game("unknown_event"),
+	];
+	let sets = sets(&games, None);
+	assert_eq!(
+		sets.iter()
+			.map(|s| s.games.iter().map(|g| g.index).collect::<Vec<_>>())
+			.collect::<Vec<_>>(),
+		vec![vec![0], vec![1, 3], vec![2]],
+	);
+	assert_eq!(
+		sets[1].score,
+		vec![
+			(PlayerKey::Port(Port::P1), 2),
+			(PlayerKey::Port(Port::P2), 0),
+		]
+	);
+	assert!(sets.iter().all(|s| s.id.is_none() && s.missing.is_empty()));
+}
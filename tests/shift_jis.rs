@@ -1,3 +1,12 @@
+use std::io::Cursor;
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	game::shift_jis::{glyph, MeleeString},
+	io::slippi,
+};
+
 mod common;
 use common::game;
 
@@ -19,3 +28,110 @@ fn crazy_name_tags() {
 	assert_eq!(tag3.to_normalized(), "A $ホ ぬヅ。");
 	assert_eq!(tag4.to_normalized(), "!!!!!!!!");
 }
+
+#[test]
+fn display() {
+	let game = game("crazy_name_tags");
+	let tags: Vec<_> = game
+		.start
+		.players
+		.iter()
+		.map(|p| p.name_tag.as_ref().unwrap())
+		.collect();
+	assert_eq!(
+		tags.iter().map(|t| t.to_display()).collect::<Vec<_>>(),
+		vec!["! CLOWN", "C@ぞ~", "A $ホ ぬヅ。", "!!!!!!!!"]
+	);
+
+	// encoding reproduces the original bytes
+	for t in &tags {
+		assert_eq!(t.name_tag_bytes().unwrap(), t.bytes().unwrap().to_vec());
+	}
+
+	assert_eq!(MeleeString("漢".to_string()).to_display(), "\u{FFFD}");
+	assert!(MeleeString("漢".to_string()).name_tag_bytes().is_err());
+	assert!(MeleeString("☃".to_string()).bytes().is_err());
+}
+
+#[test]
+fn from_display() {
+	let tag = MeleeString::from_display("C@ぞ~").unwrap();
+	assert_eq!(tag.as_str(), "C＠ぞ～");
+	assert_eq!(
+		tag.name_tag_bytes().unwrap(),
+		[0x43, 0x81, 0x97, 0x82, 0xBC, 0x81, 0x60]
+	);
+	assert_eq!(tag.to_display(), "C@ぞ~");
+
+	assert_eq!(glyph(0x8197), Some('@'));
+	assert_eq!(glyph(0x41), Some('A'));
+	assert_eq!(glyph(0x21), None);
+	assert_eq!(glyph(0x889F), None); // kanji
+
+	assert!(MeleeString::from_display("漢字").is_err());
+	assert!(MeleeString::from_display("é").is_err());
+}
+
+#[test]
+fn melee_glyphs() {
+	// code page 932 decodes these as full-width forms or different dashes
+	assert_eq!(glyph(0x818F), Some('¥'));
+	assert_eq!(glyph(0x8191), Some('¢'));
+	assert_eq!(glyph(0x8192), Some('£'));
+	assert_eq!(glyph(0x8150), Some('¯'));
+	assert_eq!(glyph(0x815C), Some('—'));
+	assert_eq!(glyph(0x8161), Some('‖'));
+
+	// code page 932 would encode `¥` as the single byte 0x5C, which Melee can't show
+	let tag = MeleeString::from_display("¥100").unwrap();
+	assert_eq!(
+		tag.name_tag_bytes().unwrap(),
+		[0x81, 0x8F, 0x31, 0x30, 0x30]
+	);
+	assert_eq!(tag.to_display(), "¥100");
+	assert_eq!(MeleeString("￥".to_string()).to_display(), "¥");
+}
+
+#[test]
+fn halfwidth_kana() {
+	let tag = MeleeString::from_display("ｶﾞﾝﾊﾟﾚ｡").unwrap();
+	assert_eq!(tag.as_str(), "ガンパレ。");
+	assert_eq!(tag.to_display(), "ガンパレ。");
+
+	// marks that can't combine are kept as-is
+	assert_eq!(MeleeString::from_display("ｱﾞ").unwrap().as_str(), "ア゛");
+	assert_eq!(MeleeString::from_display("ｳﾞ").unwrap().as_str(), "ヴ");
+}
+
+#[test]
+fn round_trip() {
+	let mut game = game("v3.12");
+	let tag = MeleeString::from_display("¥ ｶﾞ!").unwrap();
+	game.start.players[0].name_tag = Some(tag.clone());
+
+	let mut buf = vec![];
+	slippi::write(&mut buf, &game).unwrap();
+	let game = slippi::read(Cursor::new(buf), None).unwrap();
+	let actual = game.start.players[0].name_tag.as_ref().unwrap();
+	assert_eq!(actual.to_display(), "¥ ガ!");
+	assert_eq!(
+		actual.name_tag_bytes().unwrap(),
+		tag.name_tag_bytes().unwrap()
+	);
+}
+
+#[test]
+fn round_trip_unsupported() {
+	// a parsed tag with a single-byte `!`, which Melee's font can't show
+	let tag = MeleeString::try_from(&[0x21, 0x41, 0x81, 0x8F][..]).unwrap();
+	assert!(tag.name_tag_bytes().is_err());
+
+	let mut game = game("v3.12");
+	game.start.players[0].name_tag = Some(tag.clone());
+	let mut buf = vec![];
+	slippi::write(&mut buf, &game).unwrap();
+	let game = slippi::read(Cursor::new(buf), None).unwrap();
+	let actual = game.start.players[0].name_tag.as_ref().unwrap();
+	assert_eq!(actual, &tag);
+	assert_eq!(actual.bytes().unwrap()[..], [0x21, 0x41, 0x81, 0x8F]);
+}
@@ -0,0 +1,72 @@
+use std::fs;
+
+use pretty_assertions::assert_eq;
+
+use peppi::{
+	game::Game as _,
+	io::slippi::de::{parse_event, parse_header, parse_start, Event, ParseState},
+};
+
+mod common;
+use common::{game, get_path};
+
+/// Parses `name` event-by-event, calling `f` after each event.
+fn parse(name: &str, mut f: impl FnMut(&ParseState)) {
+	let bytes = fs::read(get_path(name)).unwrap();
+	let mut r = &bytes[..];
+	parse_header(&mut r, None).unwrap();
+	let mut state = parse_start(&mut r, None).unwrap();
+	while parse_event(&mut r, &mut state, None).unwrap() != Event::GameEnd as u8 {
+		f(&state);
+	}
+	f(&state);
+}
+
+#[test]
+fn snapshot() {
+	for name in ["game", "ics2", "items", "v0.1", "v2.0", "v3.18"] {
+		let expected = game(name);
+		let version = expected.start.slippi.version;
+		let mut events = 0;
+		parse(name, |state| {
+			events += 1;
+			if events % 5000 != 0 && state.end().is_none() {
+				return;
+			}
+			let snapshot = state.snapshot();
+			assert_eq!(snapshot.len(), state.closed_frames());
+			for idx in 0..snapshot.len() {
+				assert_eq!(
+					snapshot.transpose_one(idx, version),
+					expected.frames.transpose_one(idx, version)
+				);
+			}
+		});
+	}
+}
+
+#[test]
+fn incremental() {
+	for name in ["ics2", "items", "v3.18"] {
+		let expected = game(name);
+		let version = expected.start.slippi.version;
+		let mut snapshotted = 0;
+		parse(name, |state| {
+			let closed = state.closed_frames();
+			if closed - snapshotted < 100 && state.end().is_none() {
+				return;
+			}
+			// only the frames closed since the last snapshot
+			let tail = state.frames().snapshot(snapshotted, closed - snapshotted);
+			assert_eq!(tail.len(), closed - snapshotted);
+			for idx in 0..tail.len() {
+				assert_eq!(
+					tail.transpose_one(idx, version),
+					expected.frames.transpose_one(snapshotted + idx, version)
+				);
+			}
+			snapshotted = closed;
+		});
+		assert_eq!(snapshotted, expected.frames.len());
+	}
+}
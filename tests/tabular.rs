@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use pretty_assertions::assert_eq;
+use serde_json::{Map, Value};
+
+use peppi::{
+	frame::Rollbacks,
+	game::immutable::Game,
+	io::tabular::{self, Format, Opts},
+};
+
+mod common;
+use common::game;
+
+fn csv(name: &str, opts: &Opts) -> (Vec<String>, Vec<Vec<String>>) {
+	let mut buf = vec![];
+	tabular::write(&mut buf, game(name), Some(opts)).unwrap();
+	let mut lines = std::str::from_utf8(&buf).unwrap().lines();
+	let header = lines.next().unwrap().split(',').map(String::from).collect();
+	let rows = lines
+		.map(|l| l.split(',').map(String::from).collect())
+		.collect();
+	(header, rows)
+}
+
+fn json_lines(name: &str, opts: &Opts) -> Vec<Map<String, Value>> {
+	let mut buf = vec![];
+	tabular::write(&mut buf, game(name), Some(opts)).unwrap();
+	std::str::from_utf8(&buf)
+		.unwrap()
+		.lines()
+		.map(|l| serde_json::from_str(l).unwrap())
+		.collect()
+}
+
+fn column(game: &Game, name: &str) -> String {
+	let port = game.frames.ports[0].port.to_string().to_lowercase();
+	format!("{}_{}", port, name)
+}
+
+#[test]
+fn csv_columns() {
+	let expected = game("v3.12");
+	let (header, rows) = csv("v3.12", &Default::default());
+	assert_eq!(rows.len(), expected.frames.len());
+	assert!(rows.iter().all(|r| r.len() == header.len()));
+	assert_eq!(header[0], "id");
+	assert!(header.contains(&"start_random_seed".to_string()));
+	assert!(header.iter().all(|h| !h.starts_with("item")));
+
+	let x = header
+		.iter()
+		.position(|h| *h == column(&expected, "leader_post_position_x"))
+		.unwrap();
+	assert_eq!(
+		rows.iter()
+			.map(|r| r[x].parse::<f32>().unwrap())
+			.collect::<Vec<_>>(),
+		expected.frames.ports[0]
+			.leader
+			.post
+			.position
+			.x
+			.values()
+			.as_slice()
+	);
+}
+
+#[test]
+fn version_gated() {
+	// `start`, `end` & post-frame `velocities` were added in v2.2, v3.0 & v3.5
+	let (header, _) = csv("game", &Default::default());
+	assert!(header.iter().all(|h| !h.starts_with("start_")));
+	assert!(header.iter().all(|h| !h.starts_with("end_")));
+	assert!(header.iter().all(|h| !h.contains("_post_velocities_")));
+
+	let (header, _) = csv("v3.12", &Default::default());
+	assert!(header.iter().any(|h| h.starts_with("end_")));
+	assert!(header.iter().any(|h| h.contains("_post_velocities_")));
+}
+
+#[test]
+fn follower() {
+	let expected = game("ics2");
+	let (header, rows) = csv("ics2", &Default::default());
+	for p in expected.frames.ports.iter() {
+		let port = p.port.to_string().to_lowercase();
+		let name = format!("{}_follower_post_position_x", port);
+		let idx = header.iter().position(|h| *h == name);
+		let Some(follower) = p.follower.as_ref() else {
+			assert_eq!(idx, None);
+			continue;
+		};
+		let idx = idx.unwrap();
+		for (i, row) in rows.iter().enumerate() {
+			match follower.validity.as_ref().is_none_or(|v| v.get_bit(i)) {
+				true => assert_eq!(
+					row[idx].parse::<f32>().unwrap(),
+					follower.post.position.x.value(i)
+				),
+				false => assert_eq!(row[idx], ""),
+			}
+		}
+	}
+}
+
+#[test]
+fn json() {
+	let expected = game("v3.12");
+	let (header, _) = csv("v3.12", &Default::default());
+	let rows = json_lines(
+		"v3.12",
+		&Opts {
+			format: Format::JsonLines,
+			..Default::default()
+		},
+	);
+	assert_eq!(rows.len(), expected.frames.len());
+	for (i, row) in rows.iter().enumerate() {
+		assert_eq!(
+			row.keys().collect::<Vec<_>>(),
+			header.iter().collect::<Vec<_>>()
+		);
+		assert_eq!(row["id"], expected.frames.id.value(i));
+		assert_eq!(
+			row[&column(&expected, "leader_pre_position_y")]
+				.as_f64()
+				.unwrap() as f32,
+			expected.frames.ports[0].leader.pre.position.y.value(i)
+		);
+	}
+}
+
+#[test]
+fn rollbacks() {
+	let expected = game("ics2");
+	let unique: HashSet<_> = expected.frames.id.values().iter().collect();
+	assert!(unique.len() < expected.frames.len());
+
+	for format in [Format::Csv, Format::JsonLines] {
+		let opts = Opts {
+			format,
+			rollbacks: Some(Rollbacks::ExceptLast),
+		};
+		let ids: Vec<i64> = match format {
+			Format::Csv => csv("ics2", &opts)
+				.1
+				.iter()
+				.map(|r| r[0].parse().unwrap())
+				.collect(),
+			Format::JsonLines => json_lines("ics2", &opts)
+				.iter()
+				.map(|r| r["id"].as_i64().unwrap())
+				.collect(),
+		};
+		assert_eq!(ids.len(), unique.len());
+		assert!(ids.windows(2).all(|w| w[0] < w[1]));
+	}
+}
@@ -0,0 +1,83 @@
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{Cursor, Write},
+	path::PathBuf,
+};
+
+use peppi::io::slippi::{self, live::Tail, SlpWriter};
+
+mod common;
+use common::{replays, temp_dir};
+
+/// Test replays that parse, i.e. all but the deliberately broken ones.
+fn readable() -> impl Iterator<Item = PathBuf> {
+	replays().into_iter().filter(|p| {
+		!["unknown_event.slp", "corrupt.slp"].contains(&p.file_name().unwrap().to_str().unwrap())
+	})
+}
+
+#[test]
+fn live() {
+	let dir = temp_dir("writer");
+	for path in readable() {
+		let bytes = fs::read(&path).unwrap();
+		let game = slippi::read(Cursor::new(&bytes), None).unwrap();
+		if game.quirks.is_some_and(|q| q.double_game_end) {
+			// live parsing stops at the first Game End
+			continue;
+		}
+
+		let live_path = dir.join(path.file_name().unwrap());
+		File::create(&live_path).unwrap();
+		let mut file = OpenOptions::new().append(true).open(&live_path).unwrap();
+		let mut tail = Tail::open(&live_path).unwrap();
+		let mut writer = SlpWriter::new(Cursor::new(vec![])).unwrap();
+		for chunk in bytes.chunks(1021) {
+			file.write_all(chunk).unwrap();
+			tail.poll(None).unwrap();
+			if let Some(state) = tail.state() {
+				writer.update(state).unwrap();
+			}
+		}
+		assert!(writer.is_ended(), "{:?}", path);
+		assert_eq!(writer.frames(), game.frames.len());
+
+		let written = writer.finish(game.metadata.as_ref()).unwrap().into_inner();
+		assert!(written == bytes, "{:?}", path);
+	}
+}
+
+#[test]
+fn game() {
+	for path in readable() {
+		let bytes = fs::read(&path).unwrap();
+		let game = slippi::read(Cursor::new(&bytes), None).unwrap();
+
+		let mut writer = SlpWriter::new(Cursor::new(vec![])).unwrap();
+		writer.write_game(&game).unwrap();
+		// already written, so this does nothing
+		writer.write_game(&game).unwrap();
+		let written = writer.finish(game.metadata.as_ref()).unwrap().into_inner();
+		assert!(written == bytes, "{:?}", path);
+	}
+}
+
+#[test]
+fn unfinished() {
+	let bytes = fs::read("tests/data/game.slp").unwrap();
+	let game = slippi::read(Cursor::new(&bytes), None).unwrap();
+
+	let mut writer = SlpWriter::new(Cursor::new(vec![])).unwrap();
+	let mut partial = slippi::read(Cursor::new(&bytes), None).unwrap();
+	partial.end = None;
+	writer.write_game(&partial).unwrap();
+	assert!(!writer.is_ended());
+
+	// without a Game End or metadata, but still readable
+	let written = writer.finish(None).unwrap().into_inner();
+	let game2 = slippi::read(Cursor::new(&written), None).unwrap();
+	assert_eq!(game2.start, game.start);
+	assert_eq!(game2.end, None);
+	assert_eq!(game2.frames.len(), game.frames.len());
+	assert_eq!(game2.metadata, None);
+}